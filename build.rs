@@ -0,0 +1,19 @@
+use std::process::Command;
+
+/// Exposes the commit the binary is built from as `GIT_SHA`, for `GET /version`.
+/// Builds without the repository (e.g. in Docker) can pass it in the `GIT_SHA` environment variable.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    let sha = std::env::var("GIT_SHA").ok().filter(|sha| !sha.is_empty()).or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_owned())
+    });
+    println!("cargo:rustc-env=GIT_SHA={}", sha.unwrap_or_else(|| "unknown".to_owned()));
+}
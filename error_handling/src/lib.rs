@@ -1,24 +1,51 @@
 #![allow(dead_code)]
 
+use serde::Serialize;
 use warp::body::BodyDeserializeError;
 use warp::cors::CorsForbidden;
+use warp::http::header::{HeaderValue, CONTENT_TYPE, RETRY_AFTER};
 use warp::http::StatusCode;
-use warp::reject::Reject;
+use warp::reject::{
+    InvalidHeader, InvalidQuery, LengthRequired, MethodNotAllowed, MissingHeader, PayloadTooLarge, Reject, UnsupportedMediaType,
+};
+use warp::reply::Response;
 use warp::{Rejection, Reply};
 
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Why the value of a single input field was rejected.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
 #[derive(Debug)]
 pub enum ServiceError {
     EnvVarUnset,
     ParseError(std::num::ParseIntError),
     MissingParams,
+    UnknownParam(String),
+    InvalidParam(String),
     InvalidParamsRange,
     ObjectNotFound,
     DbQueryError,
     ExternalApiError,
+    /// The external service timed out, couldn't be reached or failed on its side, worth retrying.
+    ExternalApiUnavailable,
     AuthCredsMissing,
     ConflictInDb,
     AuthTokenEncoderErr,
     AuthTokenMissingOrInvalid,
+    PermissionDenied,
+    EmailNotVerified,
+    InvalidStatusTransition(String),
+    ConcurrentModification,
+    ValidationFailed(Vec<FieldError>),
+    /// Seconds until the caller's bucket has a token again.
+    RateLimited(u64),
+    /// Seconds until the account accepts logins again.
+    AccountLocked(u64),
 }
 
 impl Reject for ServiceError {}
@@ -29,70 +56,189 @@ impl std::fmt::Display for ServiceError {
             Self::EnvVarUnset => write!(f, "Case reported to admin. Please try again later."),
             Self::ParseError(err) => write!(f, "Failed to parse parameter: {}", err),
             Self::MissingParams => write!(f, "Missing parameter"),
+            Self::UnknownParam(name) => write!(f, "Unknown query parameter: {}", name),
+            Self::InvalidParam(msg) => write!(f, "Invalid query parameter: {}", msg),
             Self::InvalidParamsRange => write!(f, "Invalid parameters range"),
             Self::ObjectNotFound => write!(f, "Not found"),
             Self::DbQueryError => write!(f, "Query couldn't be executed"),
             Self::ExternalApiError => write!(f, "Error fetching data from external service"),
-            Self::AuthCredsMissing => write!(f, ""),
+            Self::ExternalApiUnavailable => write!(f, "External service unavailable, please try again later"),
+            Self::AuthCredsMissing => write!(f, "Invalid credentials"),
             Self::ConflictInDb => write!(f, "Already exists"),
             Self::AuthTokenEncoderErr => write!(f, "Case reported to admin. Please try again later."),
-            Self::AuthTokenMissingOrInvalid => write!(f, ""),
+            Self::AuthTokenMissingOrInvalid => write!(f, "Missing, expired or revoked access token"),
+            Self::PermissionDenied => write!(f, "Permission denied"),
+            Self::EmailNotVerified => write!(f, "Email not verified"),
+            Self::InvalidStatusTransition(msg) => write!(f, "Invalid status transition: {}", msg),
+            Self::ConcurrentModification => write!(f, "Modified concurrently, please reload and try again"),
+            Self::ValidationFailed(errors) => {
+                let errors: Vec<String> = errors.iter().map(|err| format!("{}: {}", err.field, err.message)).collect();
+                write!(f, "Validation failed: {}", errors.join(", "))
+            }
+            Self::RateLimited(secs) => write!(f, "Too many requests, retry in {} seconds", secs),
+            Self::AccountLocked(secs) => write!(f, "Account locked after too many failed logins, retry in {} seconds", secs),
         }
     }
 }
 
-pub async fn handle_err(r: Rejection) -> Result<impl Reply, Rejection> {
-    if let Some(err) = r.find::<CorsForbidden>() {
-        return Ok(warp::reply::with_status(err.to_string(), StatusCode::FORBIDDEN));
-    }
-
-    if let Some(err) = r.find::<BodyDeserializeError>() {
-        return Ok(warp::reply::with_status(err.to_string(), StatusCode::UNPROCESSABLE_ENTITY));
-    };
-
-    if let Some(ServiceError::EnvVarUnset) = r.find() {
-        return Ok(warp::reply::with_status(
-            ServiceError::EnvVarUnset.to_string(),
-            StatusCode::INTERNAL_SERVER_ERROR,
-        ));
+impl ServiceError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::EnvVarUnset | Self::DbQueryError | Self::AuthTokenEncoderErr => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::UnknownParam(_) | Self::InvalidParam(_) | Self::InvalidParamsRange => StatusCode::BAD_REQUEST,
+            Self::ParseError(_) | Self::MissingParams | Self::ValidationFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::ObjectNotFound => StatusCode::NOT_FOUND,
+            Self::ExternalApiError | Self::ExternalApiUnavailable => StatusCode::BAD_GATEWAY,
+            Self::AuthCredsMissing | Self::AuthTokenMissingOrInvalid => StatusCode::UNAUTHORIZED,
+            Self::PermissionDenied | Self::EmailNotVerified => StatusCode::FORBIDDEN,
+            Self::ConflictInDb | Self::InvalidStatusTransition(_) | Self::ConcurrentModification => StatusCode::CONFLICT,
+            Self::RateLimited(_) | Self::AccountLocked(_) => StatusCode::TOO_MANY_REQUESTS,
+        }
     }
 
-    if let Some(ServiceError::DbQueryError) = r.find() {
-        return Ok(warp::reply::with_status(
-            ServiceError::DbQueryError.to_string(),
-            StatusCode::INTERNAL_SERVER_ERROR,
-        ));
+    /// Machine-readable error code, stable across releases unlike the detail message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::EnvVarUnset => "service_misconfigured",
+            Self::ParseError(_) => "parse_error",
+            Self::MissingParams => "missing_parameters",
+            Self::UnknownParam(_) => "unknown_parameter",
+            Self::InvalidParam(_) => "invalid_parameter",
+            Self::InvalidParamsRange => "invalid_parameters_range",
+            Self::ObjectNotFound => "not_found",
+            Self::DbQueryError => "database_error",
+            Self::ExternalApiError => "external_service_error",
+            Self::ExternalApiUnavailable => "external_service_unavailable",
+            Self::AuthCredsMissing => "invalid_credentials",
+            Self::ConflictInDb => "conflict",
+            Self::AuthTokenEncoderErr => "token_encoding_failed",
+            Self::AuthTokenMissingOrInvalid => "invalid_token",
+            Self::PermissionDenied => "permission_denied",
+            Self::EmailNotVerified => "email_not_verified",
+            Self::InvalidStatusTransition(_) => "invalid_status_transition",
+            Self::ConcurrentModification => "concurrent_modification",
+            Self::ValidationFailed(_) => "validation_failed",
+            Self::RateLimited(_) => "rate_limited",
+            Self::AccountLocked(_) => "account_locked",
+        }
     }
 
-    if let Some(ServiceError::ObjectNotFound) = r.find() {
-        return Ok(warp::reply::with_status(String::default(), StatusCode::NOT_FOUND));
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::RateLimited(secs) | Self::AccountLocked(secs) => Some(*secs),
+            _ => None,
+        }
     }
+}
 
-    if let Some(ServiceError::AuthCredsMissing) = r.find() {
-        return Ok(warp::reply::with_status(String::default(), StatusCode::UNAUTHORIZED));
-    }
+/// Error body in the RFC 7807 `application/problem+json` format, extended with `code`, `request_id` and `errors`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+    pub code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<FieldError>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+}
 
-    if let Some(ServiceError::AuthTokenMissingOrInvalid) = r.find() {
-        return Ok(warp::reply::with_status(String::default(), StatusCode::UNAUTHORIZED));
+impl Problem {
+    pub fn new(status: StatusCode, code: &'static str, detail: impl ToString) -> Self {
+        Problem {
+            kind: "about:blank",
+            title: status.canonical_reason().unwrap_or_default(),
+            status: status.as_u16(),
+            detail: detail.to_string(),
+            code,
+            request_id: None,
+            errors: None,
+            retry_after: None,
+        }
     }
+}
 
-    if let Some(ServiceError::ConflictInDb) = r.find() {
-        return Ok(warp::reply::with_status(
-            ServiceError::ConflictInDb.to_string(),
-            StatusCode::CONFLICT,
-        ));
+impl From<&ServiceError> for Problem {
+    fn from(err: &ServiceError) -> Self {
+        let mut problem = Problem::new(err.status(), err.code(), err);
+        if let ServiceError::ValidationFailed(errors) = err {
+            problem.errors = Some(errors.clone());
+        }
+        problem.retry_after = err.retry_after();
+        problem
     }
+}
 
-    if let Some(ServiceError::AuthTokenEncoderErr) = r.find() {
-        return Ok(warp::reply::with_status(
-            ServiceError::AuthTokenEncoderErr.to_string(),
-            StatusCode::INTERNAL_SERVER_ERROR,
-        ));
+impl Reply for Problem {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut res = warp::reply::with_status(warp::reply::json(&self), status).into_response();
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/problem+json"));
+        if let Some(secs) = self.retry_after {
+            res.headers_mut().insert(RETRY_AFTER, HeaderValue::from(secs));
+        }
+        // kept for `with_request_id`, which fills in the id once the request's one is known
+        res.extensions_mut().insert(self);
+        res
     }
+}
 
+fn problem_from(r: &Rejection) -> Problem {
     if let Some(err) = r.find::<ServiceError>() {
-        return Ok(warp::reply::with_status(err.to_string(), StatusCode::UNPROCESSABLE_ENTITY));
+        return err.into();
+    }
+    if let Some(err) = r.find::<CorsForbidden>() {
+        return Problem::new(StatusCode::FORBIDDEN, "cors_forbidden", err);
+    }
+    if let Some(err) = r.find::<BodyDeserializeError>() {
+        return Problem::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_body", err);
+    }
+    if let Some(err) = r.find::<PayloadTooLarge>() {
+        return Problem::new(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", err);
+    }
+    if let Some(err) = r.find::<LengthRequired>() {
+        return Problem::new(StatusCode::LENGTH_REQUIRED, "length_required", err);
+    }
+    if let Some(err) = r.find::<UnsupportedMediaType>() {
+        return Problem::new(StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_media_type", err);
     }
+    if let Some(err) = r.find::<MissingHeader>() {
+        return Problem::new(StatusCode::BAD_REQUEST, "missing_header", err);
+    }
+    if let Some(err) = r.find::<InvalidHeader>() {
+        return Problem::new(StatusCode::BAD_REQUEST, "invalid_header", err);
+    }
+    if let Some(err) = r.find::<InvalidQuery>() {
+        return Problem::new(StatusCode::BAD_REQUEST, "invalid_query", err);
+    }
+    if let Some(err) = r.find::<MethodNotAllowed>() {
+        return Problem::new(StatusCode::METHOD_NOT_ALLOWED, "method_not_allowed", err);
+    }
+    if r.is_not_found() {
+        return Problem::new(StatusCode::NOT_FOUND, "route_not_found", "Route not found");
+    }
+    Problem::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "Unhandled error")
+}
+
+pub async fn handle_err(r: Rejection) -> Result<Response, Rejection> {
+    Ok(problem_from(&r).into_response())
+}
 
-    Ok(warp::reply::with_status("Route not found".to_string(), StatusCode::NOT_FOUND))
+/// Echoes the request id in the `X-Request-Id` header and in the body of error responses.
+pub fn with_request_id<R: Reply>(request_id: String, reply: R) -> Response {
+    let mut res = reply.into_response();
+    if let Some(mut problem) = res.extensions_mut().remove::<Problem>() {
+        problem.request_id = Some(request_id.clone());
+        *res.body_mut() = warp::reply::json(&problem).into_response().into_body();
+    }
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    res
 }
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS messages_question_idx;
+DROP TABLE IF EXISTS messages;
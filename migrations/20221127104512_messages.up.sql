@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS messages (
+    _id UUID UNIQUE DEFAULT gen_random_uuid(),
+    id serial PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT NOW(),
+    content TEXT NOT NULL,
+    kind MSG_TYPE DEFAULT 'other',
+    question UUID NOT NULL REFERENCES questions (_id) ON DELETE CASCADE,
+    author UUID REFERENCES users (_id)
+);
+
+CREATE INDEX IF NOT EXISTS messages_question_idx ON messages (question, created_at);
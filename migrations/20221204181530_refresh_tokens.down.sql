@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS refresh_tokens_family_idx;
+DROP TABLE IF EXISTS refresh_tokens;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    _id UUID UNIQUE DEFAULT gen_random_uuid(),
+    id serial PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT NOW(),
+    token_hash TEXT UNIQUE NOT NULL,
+    family UUID NOT NULL,
+    user_id UUID NOT NULL REFERENCES users (_id) ON DELETE CASCADE,
+    expires_at TIMESTAMP NOT NULL,
+    rotated_at TIMESTAMP,
+    revoked_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS refresh_tokens_family_idx ON refresh_tokens (family);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    jti UUID PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT NOW(),
+    user_id UUID REFERENCES users (_id) ON DELETE CASCADE,
+    expires_at TIMESTAMP NOT NULL
+);
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS sessions_revoked_at TIMESTAMP;
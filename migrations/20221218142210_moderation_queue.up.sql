@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS moderation_queue (
+    _id UUID UNIQUE DEFAULT gen_random_uuid(),
+    id serial PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT NOW(),
+    content TEXT NOT NULL,
+    reviewed_at TIMESTAMP,
+    reviewed_by UUID REFERENCES users (_id) ON DELETE SET NULL
+);
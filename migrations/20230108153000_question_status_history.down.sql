@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS question_status_history_question_idx;
+DROP TABLE IF EXISTS question_status_history;
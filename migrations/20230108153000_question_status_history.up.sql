@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS question_status_history (
+    _id UUID UNIQUE DEFAULT gen_random_uuid(),
+    id serial PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT NOW(),
+    question UUID NOT NULL REFERENCES questions (_id) ON DELETE CASCADE,
+    actor UUID REFERENCES users (_id) ON DELETE SET NULL,
+    old_status QUESTION_STATUS NOT NULL,
+    new_status QUESTION_STATUS NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS question_status_history_question_idx ON question_status_history (question, created_at);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS questions_assignee_idx;
+ALTER TABLE questions DROP COLUMN IF EXISTS assigned_at;
+ALTER TABLE questions DROP COLUMN IF EXISTS assignee;
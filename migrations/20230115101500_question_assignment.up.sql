@@ -0,0 +1,4 @@
+ALTER TABLE questions ADD COLUMN IF NOT EXISTS assignee UUID REFERENCES users (_id) ON DELETE SET NULL;
+ALTER TABLE questions ADD COLUMN IF NOT EXISTS assigned_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS questions_assignee_idx ON questions (assignee);
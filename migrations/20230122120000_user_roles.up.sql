@@ -0,0 +1,20 @@
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'user_role') THEN
+        CREATE TYPE user_role AS ENUM ('customer', 'agent', 'moderator', 'admin');
+    END IF;
+END
+$$;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role USER_ROLE NOT NULL DEFAULT 'customer';
+
+UPDATE users SET role = CASE
+    WHEN is_superuser THEN 'admin'::user_role
+    WHEN is_moderator THEN 'moderator'::user_role
+    WHEN is_staff THEN 'agent'::user_role
+    ELSE 'customer'::user_role
+END;
+
+ALTER TABLE users DROP COLUMN IF EXISTS is_moderator;
+ALTER TABLE users DROP COLUMN IF EXISTS is_staff;
+ALTER TABLE users DROP COLUMN IF EXISTS is_superuser;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS account_tokens_user_idx;
+DROP TABLE IF EXISTS account_tokens;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS account_tokens (
+    _id UUID UNIQUE DEFAULT gen_random_uuid(),
+    id serial PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT NOW(),
+    token_hash TEXT UNIQUE NOT NULL,
+    purpose TEXT NOT NULL,
+    user_id UUID NOT NULL REFERENCES users (_id) ON DELETE CASCADE,
+    email VARCHAR(64),
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS account_tokens_user_idx ON account_tokens (user_id, purpose);
@@ -0,0 +1,4 @@
+DELETE FROM moderation_queue WHERE content IS NULL;
+ALTER TABLE moderation_queue ALTER COLUMN content SET NOT NULL;
+ALTER TABLE moderation_queue DROP COLUMN IF EXISTS message;
+ALTER TABLE moderation_queue DROP COLUMN IF EXISTS question;
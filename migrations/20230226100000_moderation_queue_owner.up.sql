@@ -0,0 +1,4 @@
+ALTER TABLE moderation_queue ADD COLUMN IF NOT EXISTS question UUID REFERENCES questions (_id) ON DELETE CASCADE;
+ALTER TABLE moderation_queue ADD COLUMN IF NOT EXISTS message UUID REFERENCES messages (_id) ON DELETE CASCADE;
+-- rows queued before the owners were recorded only have the text
+ALTER TABLE moderation_queue ALTER COLUMN content DROP NOT NULL;
@@ -0,0 +1,3 @@
+-- when the bucket is full again, from then on it holds nothing a new bucket wouldn't and can be dropped
+ALTER TABLE rate_limit_buckets ADD COLUMN IF NOT EXISTS full_at TIMESTAMP NOT NULL DEFAULT NOW();
+CREATE INDEX IF NOT EXISTS rate_limit_buckets_full_at_idx ON rate_limit_buckets (full_at);
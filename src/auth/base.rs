@@ -1,6 +1,6 @@
-use crate::types::user::UserTknDetails;
+use crate::types::{auth::Claims, user::UserTknDetails};
 
 pub trait AuthProvider: std::fmt::Debug + Clone + std::marker::Send {
-    fn parse_token(&self, tkn: String) -> Option<UserTknDetails>;
+    fn parse_token(&self, tkn: String) -> Option<Claims>;
     fn issue_token(&self, u: UserTknDetails) -> Option<String>;
 }
@@ -1,9 +1,8 @@
-use std::env::VarError;
-
 use super::base::AuthProvider;
 use crate::types::{auth::Claims, user::UserTknDetails};
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use uuid::Uuid;
 
 const TOKEN_EXP_MINS: i64 = 5;
 
@@ -13,18 +12,20 @@ pub struct JWTAuth {
 }
 
 impl JWTAuth {
-    pub fn new() -> Result<Self, VarError> {
-        let secret = std::env::var("AUTH_SECRET")?;
-        Ok(JWTAuth { secret })
+    pub fn new(secret: String) -> Self {
+        JWTAuth { secret }
     }
 }
 
 impl AuthProvider for JWTAuth {
     fn issue_token(&self, u: UserTknDetails) -> Option<String> {
+        let now = Utc::now();
         let claims = Claims {
-            exp: (Utc::now() + Duration::minutes(TOKEN_EXP_MINS)).timestamp() as usize,
+            exp: (now + Duration::minutes(TOKEN_EXP_MINS)).timestamp() as usize,
+            iat: now.timestamp_micros() as f64 / 1e6,
+            jti: Uuid::new_v4().to_string(),
             sub: u._id.clone(),
-            moderator: u.is_moderator,
+            role: u.role,
         };
         let tkn = encode(&Header::default(), &claims, &EncodingKey::from_secret(self.secret.as_bytes()));
         if tkn.is_err() {
@@ -33,7 +34,7 @@ impl AuthProvider for JWTAuth {
         Some(tkn.unwrap())
     }
 
-    fn parse_token(&self, tkn: String) -> Option<UserTknDetails> {
+    fn parse_token(&self, tkn: String) -> Option<Claims> {
         let tkn_data = decode::<Claims>(
             &tkn.replace("Token ", ""),
             &DecodingKey::from_secret(self.secret.as_bytes()),
@@ -42,12 +43,6 @@ impl AuthProvider for JWTAuth {
         if tkn_data.is_err() {
             return None;
         }
-        let claims = tkn_data.unwrap().claims;
-        Some({
-            UserTknDetails {
-                _id: claims.sub,
-                is_moderator: claims.moderator,
-            }
-        })
+        Some(tkn_data.unwrap().claims)
     }
 }
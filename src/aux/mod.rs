@@ -1,3 +0,0 @@
-mod text_processing;
-
-pub use text_processing::*;
\ No newline at end of file
@@ -0,0 +1,707 @@
+use crate::mail::{MailSettings, MailerKind, SmtpSettings};
+use crate::moderation::{ContentFilterKind, ContentFilterSettings, OpenCircuitPolicyKind, ResilienceSettings};
+use crate::ratelimit::{Quota, RateLimitStoreKind, RateLimits};
+use crate::storage::DbSettings;
+use crate::types::{account::VerificationPolicy, assignment::AssignmentStrategy, auth::LoginLockout};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, Command};
+use lettre::message::Mailbox;
+use lettre::Address;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A single setting: `key` is its `section.key` name in the TOML file, `env` the environment variable
+/// overriding it and `--section-key` the command line flag overriding both.
+struct Setting {
+    key: &'static str,
+    env: &'static str,
+    default: Option<&'static str>,
+    help: &'static str,
+}
+
+const fn setting(key: &'static str, env: &'static str, default: Option<&'static str>, help: &'static str) -> Setting {
+    Setting { key, env, default, help }
+}
+
+const SETTINGS: &[Setting] = &[
+    setting("server.host", "SERVER_HOST", Some("0.0.0.0"), "Address to listen on"),
+    setting("server.port", "SERVER_PORT", Some("7878"), "Port to listen on"),
+    setting(
+        "server.metrics_host",
+        "METRICS_HOST",
+        Some("127.0.0.1"),
+        "Address serving GET /metrics, keep it off the public network",
+    ),
+    setting(
+        "server.metrics_port",
+        "METRICS_PORT",
+        Some("9100"),
+        "Port serving GET /metrics",
+    ),
+    setting(
+        "server.cors_origins",
+        "CORS_ORIGINS",
+        Some("http://front-end-service:3000"),
+        "Comma separated origins allowed by CORS",
+    ),
+    setting(
+        "server.public_url",
+        "PUBLIC_URL",
+        Some("http://localhost:7878"),
+        "URL the service is reached at, used in mailed links",
+    ),
+    setting(
+        "server.shutdown_delay_secs",
+        "SHUTDOWN_DELAY_SECS",
+        Some("5"),
+        "How long to keep serving with /readyz failing before draining",
+    ),
+    setting(
+        "server.shutdown_timeout_secs",
+        "SHUTDOWN_TIMEOUT_SECS",
+        Some("30"),
+        "How long to wait for in-flight requests and background tasks",
+    ),
+    setting("log.format", "LOG_FORMAT", Some("text"), "Log format: text or json"),
+    setting(
+        "log.filter",
+        "RUST_LOG",
+        Some("customer_care=warn,warp=error"),
+        "Log filter directives",
+    ),
+    setting("database.user", "POSTGRES_USER", None, "Database user"),
+    setting("database.password", "POSTGRES_PASSWORD", None, "Database password"),
+    setting("database.host", "POSTGRES_HOST", None, "Database host"),
+    setting("database.port", "POSTGRES_PORT", Some("5432"), "Database port"),
+    setting("database.name", "POSTGRES_DB", None, "Database name"),
+    setting(
+        "database.max_connections",
+        "POSTGRES_MAX_CONNECTIONS",
+        Some("5"),
+        "Size of the connection pool",
+    ),
+    setting("auth.secret", "AUTH_SECRET", None, "Secret signing the access tokens"),
+    setting(
+        "auth.moderator_key",
+        "MODERATOR_AUTH_KEY",
+        None,
+        "Key allowing to sign up as a moderator",
+    ),
+    setting(
+        "auth.bootstrap_admin_email",
+        "BOOTSTRAP_ADMIN_EMAIL",
+        None,
+        "Account made admin at startup while there is no admin yet",
+    ),
+    setting(
+        "auth.lockout_threshold",
+        "LOGIN_LOCKOUT_THRESHOLD",
+        Some("5"),
+        "Failed logins in a row locking the account, 0 turns it off",
+    ),
+    setting(
+        "auth.lockout_base_secs",
+        "LOGIN_LOCKOUT_BASE_SECS",
+        Some("30"),
+        "First lock duration, doubling with every further failure",
+    ),
+    setting(
+        "auth.lockout_max_secs",
+        "LOGIN_LOCKOUT_MAX_SECS",
+        Some("3600"),
+        "Longest lock duration",
+    ),
+    setting(
+        "auth.email_verification",
+        "EMAIL_VERIFICATION",
+        Some("optional"),
+        "optional or required to ask questions",
+    ),
+    setting(
+        "rate_limit.store",
+        "RATE_LIMIT_STORE",
+        Some("memory"),
+        "Where the token buckets live: memory or postgres",
+    ),
+    setting(
+        "rate_limit.login",
+        "RATE_LIMIT_LOGIN",
+        Some("20/60"),
+        "Logins per client IP, <requests>/<seconds> or off",
+    ),
+    setting(
+        "rate_limit.mail",
+        "RATE_LIMIT_MAIL",
+        Some("20/600"),
+        "Requests sending mails per client IP and route, <requests>/<seconds> or off",
+    ),
+    setting(
+        "rate_limit.trusted_proxies",
+        "RATE_LIMIT_TRUSTED_PROXIES",
+        None,
+        "Comma separated proxy IPs whose X-Forwarded-For is trusted",
+    ),
+    setting(
+        "rate_limit.questions",
+        "RATE_LIMIT_QUESTIONS",
+        Some("10/60"),
+        "New questions per user, <requests>/<seconds> or off",
+    ),
+    setting(
+        "questions.auto_assign",
+        "AUTO_ASSIGN",
+        Some("off"),
+        "Assigns new questions: off, round_robin or least_loaded",
+    ),
+    setting(
+        "content_filter.backend",
+        "CONTENT_FILTER",
+        Some("apilayer"),
+        "Content filter: apilayer or local",
+    ),
+    setting(
+        "content_filter.api_key",
+        "BAD_WORDS_SERVICE_API_KEY",
+        None,
+        "Key of the remote bad words service",
+    ),
+    setting(
+        "content_filter.words_file",
+        "BAD_WORDS_FILE",
+        None,
+        "File replacing the built-in word lists",
+    ),
+    setting(
+        "content_filter.languages",
+        "BAD_WORDS_LANGUAGES",
+        None,
+        "Comma separated word lists to use, all by default",
+    ),
+    setting(
+        "content_filter.timeout_ms",
+        "BAD_WORDS_TIMEOUT_MS",
+        Some("2000"),
+        "Timeout of the remote service calls",
+    ),
+    setting(
+        "content_filter.retries",
+        "BAD_WORDS_RETRIES",
+        Some("2"),
+        "Retries of failed remote service calls",
+    ),
+    setting(
+        "content_filter.backoff_ms",
+        "BAD_WORDS_BACKOFF_MS",
+        Some("200"),
+        "First retry delay, doubling with every retry",
+    ),
+    setting(
+        "content_filter.breaker_threshold",
+        "BAD_WORDS_BREAKER_THRESHOLD",
+        Some("5"),
+        "Failures in a row opening the circuit",
+    ),
+    setting(
+        "content_filter.breaker_cooldown_secs",
+        "BAD_WORDS_BREAKER_COOLDOWN_SECS",
+        Some("30"),
+        "How long the circuit stays open",
+    ),
+    setting(
+        "content_filter.open_circuit_policy",
+        "BAD_WORDS_OPEN_CIRCUIT_POLICY",
+        Some("reject"),
+        "reject, queue or local while the circuit is open",
+    ),
+    setting("mail.backend", "MAILER", Some("log"), "Mail sender: log, file or smtp"),
+    setting("mail.dir", "MAIL_DIR", Some("mails"), "Directory of the file mail sender"),
+    setting("mail.from", "MAIL_FROM", None, "Sender address of the smtp mail sender"),
+    setting("mail.smtp_host", "SMTP_HOST", None, "SMTP relay, STARTTLS is required"),
+    setting("mail.smtp_port", "SMTP_PORT", None, "SMTP relay port"),
+    setting("mail.smtp_username", "SMTP_USERNAME", None, "SMTP user"),
+    setting("mail.smtp_password", "SMTP_PASSWORD", None, "SMTP password"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "expected text or json")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerSettings {
+    pub addr: SocketAddr,
+    pub metrics_addr: SocketAddr,
+    pub cors_origins: Vec<String>,
+    pub public_url: String,
+    pub shutdown_delay: Duration,
+    pub shutdown_timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthSettings {
+    pub secret: String,
+    pub moderator_key: String,
+    pub bootstrap_admin_email: Option<String>,
+    pub lockout: LoginLockout,
+    pub email_verification: VerificationPolicy,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitSettings {
+    pub store: RateLimitStoreKind,
+    pub limits: RateLimits,
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub server: ServerSettings,
+    pub log_filter: String,
+    pub log_format: LogFormat,
+    pub database: DbSettings,
+    pub auth: AuthSettings,
+    pub rate_limit: RateLimitSettings,
+    pub auto_assign: AssignmentStrategy,
+    pub content_filter: ContentFilterSettings,
+    pub mail: MailSettings,
+}
+
+impl Config {
+    /// Reads the settings from, in increasing priority, their defaults, the TOML file given with `--config`
+    /// (or `CONFIG_FILE`), environment variables and command line flags.
+    /// Fails with every invalid or missing setting at once rather than with the first one.
+    pub fn load() -> Result<Self, Vec<String>> {
+        Self::from_matches(command().get_matches())
+    }
+
+    fn from_matches(matches: ArgMatches) -> Result<Self, Vec<String>> {
+        let file = match matches.get_one::<PathBuf>("config") {
+            Some(path) => read_file(path).map_err(|err| vec![err])?,
+            None => HashMap::new(),
+        };
+        let mut errors: Vec<String> = file
+            .keys()
+            .filter(|key| !SETTINGS.iter().any(|s| s.key == key.as_str()))
+            .map(|key| format!("{}: unknown setting", key))
+            .collect();
+        errors.sort();
+
+        let mut r = Reader { matches, file, errors };
+        let backend = r.get::<ContentFilterKind>("content_filter.backend");
+        let config = Config {
+            server: ServerSettings {
+                addr: SocketAddr::new(
+                    r.opt("server.host").unwrap_or(IpAddr::from([0, 0, 0, 0])),
+                    r.get("server.port"),
+                ),
+                metrics_addr: SocketAddr::new(
+                    r.opt("server.metrics_host").unwrap_or(IpAddr::from([127, 0, 0, 1])),
+                    r.get("server.metrics_port"),
+                ),
+                cors_origins: r.list("server.cors_origins"),
+                public_url: r.get("server.public_url"),
+                shutdown_delay: Duration::from_secs(r.get("server.shutdown_delay_secs")),
+                shutdown_timeout: Duration::from_secs(r.get("server.shutdown_timeout_secs")),
+            },
+            log_filter: r.get("log.filter"),
+            log_format: r.get("log.format"),
+            database: DbSettings {
+                user: r.get("database.user"),
+                password: r.get("database.password"),
+                host: r.get("database.host"),
+                port: r.get("database.port"),
+                name: r.get("database.name"),
+                max_connections: r.at_least("database.max_connections", 1),
+            },
+            auth: AuthSettings {
+                secret: r.get("auth.secret"),
+                moderator_key: r.get("auth.moderator_key"),
+                bootstrap_admin_email: r.opt("auth.bootstrap_admin_email"),
+                lockout: r.lockout(),
+                email_verification: r.get("auth.email_verification"),
+            },
+            rate_limit: RateLimitSettings {
+                store: r.get("rate_limit.store"),
+                limits: RateLimits {
+                    login_per_ip: r.quota("rate_limit.login"),
+                    mail_per_ip: r.quota("rate_limit.mail"),
+                    questions_per_user: r.quota("rate_limit.questions"),
+                },
+                trusted_proxies: r.list("rate_limit.trusted_proxies"),
+            },
+            auto_assign: r.get("questions.auto_assign"),
+            content_filter: ContentFilterSettings {
+                backend,
+                api_key: match backend {
+                    ContentFilterKind::ApiLayer => r.get("content_filter.api_key"),
+                    ContentFilterKind::Local => r.opt("content_filter.api_key").unwrap_or_default(),
+                },
+                words_file: r.opt("content_filter.words_file"),
+                languages: r.list("content_filter.languages"),
+                resilience: ResilienceSettings {
+                    timeout: Duration::from_millis(r.get("content_filter.timeout_ms")),
+                    retries: r.get("content_filter.retries"),
+                    backoff: Duration::from_millis(r.get("content_filter.backoff_ms")),
+                    failure_threshold: r.at_least("content_filter.breaker_threshold", 1),
+                    cooldown: Duration::from_secs(r.get("content_filter.breaker_cooldown_secs")),
+                },
+                open_circuit_policy: r.get::<OpenCircuitPolicyKind>("content_filter.open_circuit_policy"),
+            },
+            mail: r.mail(),
+        };
+
+        match r.errors.is_empty() {
+            true => Ok(config),
+            false => Err(r.errors),
+        }
+    }
+}
+
+fn flag(key: &str) -> String {
+    key.replace(['.', '_'], "-")
+}
+
+fn command() -> Command {
+    let config = Arg::new("config")
+        .short('c')
+        .long("config")
+        .env("CONFIG_FILE")
+        .value_name("FILE")
+        .value_parser(clap::value_parser!(PathBuf))
+        .help("TOML file with the settings, overridden by environment variables and flags");
+    SETTINGS.iter().fold(
+        Command::new(env!("CARGO_PKG_NAME"))
+            .version(env!("CARGO_PKG_VERSION"))
+            .arg(config),
+        |cmd, s| {
+            let mut help = s.help.to_owned();
+            if let Some(default) = s.default {
+                help = format!("{} [default: {}]", help, default);
+            }
+            cmd.arg(
+                Arg::new(s.key)
+                    .long(flag(s.key))
+                    .env(s.env)
+                    .hide_env_values(true)
+                    .value_name("VALUE")
+                    .help(help),
+            )
+        },
+    )
+}
+
+/// Reads the TOML file into `section.key` → value, arrays becoming comma separated lists.
+fn read_file(path: &PathBuf) -> Result<HashMap<String, String>, String> {
+    let src = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let table = src
+        .parse::<toml::Table>()
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+
+    let mut values = HashMap::new();
+    for (section, value) in table {
+        let toml::Value::Table(settings) = value else {
+            return Err(format!("{}: {} is not a section", path.display(), section));
+        };
+        for (key, value) in settings {
+            let value = match value {
+                toml::Value::String(s) => s,
+                toml::Value::Array(items) => items
+                    .into_iter()
+                    .map(|item| match item {
+                        toml::Value::String(s) => s,
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(","),
+                other => other.to_string(),
+            };
+            values.insert(format!("{}.{}", section, key), value);
+        }
+    }
+    Ok(values)
+}
+
+/// Looks settings up layer by layer, collecting the errors instead of failing on the first one.
+struct Reader {
+    matches: ArgMatches,
+    file: HashMap<String, String>,
+    errors: Vec<String>,
+}
+
+impl Reader {
+    fn setting(key: &str) -> &'static Setting {
+        SETTINGS
+            .iter()
+            .find(|s| s.key == key)
+            .unwrap_or_else(|| panic!("undeclared setting {}", key))
+    }
+
+    /// The value with the highest priority and where it comes from, empty values count as unset.
+    fn raw(&self, key: &str) -> Option<(String, String)> {
+        let s = Self::setting(key);
+        let explicit = match self.matches.value_source(key) {
+            Some(ValueSource::CommandLine) => Some(format!("--{}", flag(key))),
+            Some(ValueSource::EnvVariable) => Some(s.env.to_owned()),
+            _ => None,
+        };
+        let value = match explicit {
+            Some(source) => self.matches.get_one::<String>(key).map(|v| (v.clone(), source)),
+            None => match self.file.get(key) {
+                Some(v) => Some((v.clone(), key.to_owned())),
+                None => s.default.map(|v| (v.to_owned(), format!("{} (default)", key))),
+            },
+        };
+        value.filter(|(v, _)| !v.trim().is_empty())
+    }
+
+    fn opt<T: FromStr>(&mut self, key: &str) -> Option<T>
+    where
+        T::Err: Display,
+    {
+        let (value, source) = self.raw(key)?;
+        match value.trim().parse::<T>() {
+            Ok(v) => Some(v),
+            Err(err) => {
+                self.errors.push(format!("{}: invalid value {:?}: {}", source, value, err));
+                None
+            }
+        }
+    }
+
+    fn get<T: FromStr + Default>(&mut self, key: &str) -> T
+    where
+        T::Err: Display,
+    {
+        if self.raw(key).is_none() {
+            let s = Self::setting(key);
+            self.errors.push(format!(
+                "{} is not set, set it in the config file, with {} or --{}",
+                key,
+                s.env,
+                flag(key)
+            ));
+        }
+        self.opt(key).unwrap_or_default()
+    }
+
+    /// Like `get`, but also rejects values below `min`.
+    fn at_least<T: FromStr + Default + PartialOrd + Display>(&mut self, key: &str, min: T) -> T
+    where
+        T::Err: Display,
+    {
+        let errors = self.errors.len();
+        let value = self.get(key);
+        if self.errors.len() == errors && value < min {
+            let (_, source) = self.raw(key).unwrap_or_default();
+            self.errors.push(format!("{}: must be at least {}", source, min));
+        }
+        value
+    }
+
+    fn list<T: FromStr>(&mut self, key: &str) -> Vec<T>
+    where
+        T::Err: Display,
+    {
+        let Some((value, source)) = self.raw(key) else {
+            return Vec::new();
+        };
+        let mut items = Vec::new();
+        for item in value.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            match item.parse::<T>() {
+                Ok(v) => items.push(v),
+                Err(err) => self.errors.push(format!("{}: invalid value {:?}: {}", source, item, err)),
+            }
+        }
+        items
+    }
+
+    /// A quota, `off` turning the limit off.
+    fn quota(&mut self, key: &str) -> Option<Quota> {
+        match self.raw(key) {
+            Some((value, _)) if value.trim() == "off" => None,
+            _ => self.opt(key),
+        }
+    }
+
+    fn lockout(&mut self) -> LoginLockout {
+        let lockout = LoginLockout {
+            threshold: self.get("auth.lockout_threshold"),
+            base_secs: self.get("auth.lockout_base_secs"),
+            max_secs: self.get("auth.lockout_max_secs"),
+        };
+        if lockout.base_secs > lockout.max_secs {
+            self.errors.push(format!(
+                "auth.lockout_base_secs: {} is longer than auth.lockout_max_secs {}",
+                lockout.base_secs, lockout.max_secs
+            ));
+        }
+        lockout
+    }
+
+    fn mail(&mut self) -> MailSettings {
+        match self.get::<MailerKind>("mail.backend") {
+            MailerKind::Log => MailSettings::Log,
+            MailerKind::File => MailSettings::File(self.get("mail.dir")),
+            MailerKind::Smtp => {
+                let (username, password) = (self.opt("mail.smtp_username"), self.opt("mail.smtp_password"));
+                let smtp = SmtpSettings {
+                    host: self.get("mail.smtp_host"),
+                    port: self.opt("mail.smtp_port"),
+                    credentials: username.zip(password),
+                    from: self.mail_from(),
+                };
+                MailSettings::Smtp(smtp)
+            }
+        }
+    }
+
+    fn mail_from(&mut self) -> Mailbox {
+        if self.raw("mail.from").is_none() {
+            self.errors.push("mail.from is required by the smtp mail sender".to_owned());
+        }
+        // the placeholder never gets used, the errors above fail the whole config
+        self.opt("mail.from")
+            .unwrap_or_else(|| Mailbox::new(None, Address::new("nobody", "localhost").unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, MutexGuard};
+
+    /// Serializes the tests, clap reads the environment variables of the whole process.
+    static ENV: Mutex<()> = Mutex::new(());
+
+    /// Holds `ENV` with the settings' environment variables removed, restoring them when dropped,
+    /// so a developer's own settings don't leak into the tests.
+    struct CleanEnv {
+        saved: Vec<(&'static str, Option<String>)>,
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl CleanEnv {
+        fn new() -> Self {
+            let lock = ENV.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let saved = SETTINGS
+                .iter()
+                .map(|s| s.env)
+                .chain(["CONFIG_FILE"])
+                .map(|name| (name, std::env::var(name).ok()))
+                .collect::<Vec<_>>();
+            for (name, _) in &saved {
+                std::env::remove_var(name);
+            }
+            CleanEnv { saved, _lock: lock }
+        }
+    }
+
+    impl Drop for CleanEnv {
+        fn drop(&mut self) {
+            for (name, value) in &self.saved {
+                match value {
+                    Some(value) => std::env::set_var(name, value),
+                    None => std::env::remove_var(name),
+                }
+            }
+        }
+    }
+
+    const REQUIRED: &str = r#"
+[database]
+user = "care"
+password = "secret"
+host = "localhost"
+name = "care"
+
+[auth]
+secret = "0123456789abcdef"
+moderator_key = "modkey"
+
+[content_filter]
+backend = "local"
+"#;
+
+    fn load(name: &str, toml: &str, flags: &[&str]) -> Result<Config, Vec<String>> {
+        let path = std::env::temp_dir().join(format!("customer_care_{}_{}.toml", name, std::process::id()));
+        std::fs::write(&path, toml).unwrap();
+        let mut args = vec!["customer_care".to_owned(), "--config".to_owned(), path.display().to_string()];
+        args.extend(flags.iter().map(|flag| flag.to_string()));
+        let config = Config::from_matches(command().try_get_matches_from(args).unwrap());
+        std::fs::remove_file(path).unwrap();
+        config
+    }
+
+    #[test]
+    fn flags_override_env_which_overrides_the_file() {
+        let _env = CleanEnv::new();
+        std::env::set_var("SERVER_PORT", "2000");
+        std::env::set_var("PUBLIC_URL", "https://env.example.com");
+        let toml = format!(
+            "{}\n[server]\nport = 1000\npublic_url = \"https://file.example.com\"\nshutdown_timeout_secs = 7\n",
+            REQUIRED
+        );
+        let config = load("layering", &toml, &["--server-port", "3000"]).unwrap();
+        assert_eq!(config.server.addr.port(), 3000);
+        assert_eq!(config.server.public_url, "https://env.example.com");
+        assert_eq!(config.server.shutdown_timeout, Duration::from_secs(7));
+        assert_eq!(config.log_format, LogFormat::Text);
+    }
+
+    #[test]
+    fn every_error_is_reported_at_once() {
+        let _env = CleanEnv::new();
+        let toml = "[server]\nport = \"http\"\n\n[auth]\nlockout_base_secs = 600\nlockout_max_secs = 60\n\n[nope]\nkey = 1\n";
+        let errors = load(
+            "errors",
+            toml,
+            &["--database-max-connections", "0", "--content-filter-breaker-threshold", "0"],
+        )
+        .unwrap_err();
+
+        for expected in [
+            "nope.key: unknown setting",
+            "server.port: invalid value",
+            "database.user is not set",
+            "auth.secret is not set",
+            "--database-max-connections: must be at least 1",
+            "auth.lockout_base_secs: 600 is longer than auth.lockout_max_secs 60",
+            "content_filter.api_key is not set",
+            "--content-filter-breaker-threshold: must be at least 1",
+        ] {
+            assert!(
+                errors.iter().any(|err| err.starts_with(expected)),
+                "{:?} not in {:?}",
+                expected,
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn api_key_is_only_required_by_apilayer() {
+        let _env = CleanEnv::new();
+        assert!(load("local", REQUIRED, &[]).is_ok());
+        let errors = load("apilayer", REQUIRED, &["--content-filter-backend", "apilayer"]).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("content_filter.api_key is not set"), "{:?}", errors);
+    }
+}
@@ -0,0 +1,83 @@
+use std::str::FromStr;
+use warp::http::StatusCode;
+use warp::{Rejection, Reply};
+
+use crate::storage::{QuestionStore, UserStore};
+use crate::types::assignment::AssigneeIn;
+use crate::types::role::Permission;
+use crate::types::shared::Id;
+use crate::types::user::UserTknDetails;
+use error_handling::ServiceError;
+
+pub async fn assign_question<S: QuestionStore + UserStore>(
+    id: String,
+    _: UserTknDetails,
+    db: S,
+    assignee: AssigneeIn,
+) -> Result<impl Reply, Rejection> {
+    let agent = match db.get_user(Id::from_str(&assignee.assignee).unwrap()).await {
+        Ok(agent) if agent.role.can(Permission::HandleQuestions) && agent.deactivated_at.is_none() => agent,
+        Ok(_) | Err(ServiceError::ObjectNotFound) => {
+            return Err(warp::reject::custom(ServiceError::InvalidParam(
+                "assignee must be an agent".to_owned(),
+            )))
+        }
+        Err(e) => return Err(warp::reject::custom(e)),
+    };
+    let question = db
+        .get_question(Id::from_str(&id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+    db.assign_question(Id::from_str(&question._id).unwrap(), question.assignee, Some(agent._id))
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
+
+pub async fn unassign_question<S: QuestionStore>(id: String, user: UserTknDetails, db: S) -> Result<impl Reply, Rejection> {
+    let question = db
+        .get_question(Id::from_str(&id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+    let is_assignee = question.assignee.as_ref() == Some(&user._id);
+    if !is_assignee && !user.can(Permission::AssignQuestions) {
+        return Err(warp::reject::custom(ServiceError::PermissionDenied));
+    }
+    if question.assignee.is_none() {
+        return Ok(warp::reply::with_status("", StatusCode::NO_CONTENT));
+    }
+    db.assign_question(Id::from_str(&question._id).unwrap(), question.assignee, None)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
+
+/// Lets an agent pick up an unassigned question themselves.
+pub async fn claim_question<S: QuestionStore>(id: String, user: UserTknDetails, db: S) -> Result<impl Reply, Rejection> {
+    let question = db
+        .get_question(Id::from_str(&id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+    if question.assignee.is_some() {
+        return Err(warp::reject::custom(ServiceError::ConcurrentModification));
+    }
+    db.assign_question(Id::from_str(&question._id).unwrap(), None, Some(user._id))
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
+
+pub async fn list_my_queue<S: QuestionStore>(user: UserTknDetails, db: S) -> Result<impl Reply, Rejection> {
+    let questions = db.list_assigned_questions(user._id).await.map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&questions))
+}
+
+pub async fn staff_workload<S: QuestionStore>(_: UserTknDetails, db: S) -> Result<impl Reply, Rejection> {
+    let workload = db.staff_workload().await.map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&workload))
+}
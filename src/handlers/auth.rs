@@ -1,48 +1,181 @@
-use std::future;
-
 use crate::{
     auth::AuthProvider,
-    storage::Db,
+    mail::{Mail, Mailer},
+    metrics::METRICS,
+    storage::{AccountTokenStore, TokenStore, UserStore},
     types::{
-        auth::{Creds, Token},
+        account::{AccountTokenPurpose, PasswordForgotIn, PasswordResetIn},
+        auth::{Claims, Creds, LoginLockout, LogoutIn, RefreshTokenIn, Token},
+        role::Permission,
         user::UserTknDetails,
     },
 };
 use error_handling::ServiceError;
-use tracing::instrument;
+use tokio_util::task::TaskTracker;
+use tracing::{event, instrument, Instrument, Level};
 use warp::{http::StatusCode, Filter, Rejection, Reply};
 
 pub fn parse_auth_headers() -> impl Filter<Extract = (Option<String>,), Error = warp::Rejection> + Clone {
     warp::header::optional::<String>("Authorization")
 }
 
-pub fn authenticate<T: AuthProvider>(
+pub fn authenticate_session<T: AuthProvider + Sync + 'static, S: TokenStore>(
     auth_provider: T,
-) -> impl Filter<Extract = (UserTknDetails,), Error = warp::Rejection> + Clone {
+    db: S,
+) -> impl Filter<Extract = (Claims,), Error = warp::Rejection> + Clone {
     parse_auth_headers().and_then(move |token: Option<String>| {
-        if token.is_none() {
-            return future::ready(Err(warp::reject::custom(ServiceError::AuthTokenMissingOrInvalid)));
+        let auth_provider = auth_provider.clone();
+        let db = db.clone();
+        async move {
+            let claims = token
+                .and_then(|token| auth_provider.parse_token(token))
+                .ok_or_else(|| warp::reject::custom(ServiceError::AuthTokenMissingOrInvalid))?;
+            if db.is_token_revoked(&claims).await.map_err(warp::reject::custom)? {
+                return Err(warp::reject::custom(ServiceError::AuthTokenMissingOrInvalid));
+            }
+            Ok(claims)
         }
-        match auth_provider.parse_token(token.unwrap()) {
-            None => future::ready(Err(warp::reject::custom(ServiceError::AuthTokenMissingOrInvalid))),
-            Some(user_details) => future::ready(Ok(user_details)),
+    })
+}
+
+pub fn authenticate<T: AuthProvider + Sync + 'static, S: TokenStore>(
+    auth_provider: T,
+    db: S,
+) -> impl Filter<Extract = (UserTknDetails,), Error = warp::Rejection> + Clone {
+    authenticate_session(auth_provider, db).map(|claims: Claims| claims.user_details())
+}
+
+/// Like `authenticate`, but also rejects users whose role does not grant `permission`.
+pub fn require<T: AuthProvider + Sync + 'static, S: TokenStore>(
+    auth_provider: T,
+    db: S,
+    permission: Permission,
+) -> impl Filter<Extract = (UserTknDetails,), Error = warp::Rejection> + Clone {
+    authenticate(auth_provider, db).and_then(move |user: UserTknDetails| async move {
+        match user.can(permission) {
+            true => Ok(user),
+            false => Err(warp::reject::custom(ServiceError::PermissionDenied)),
         }
     })
 }
 
-#[instrument]
-pub async fn login<T: AuthProvider>(creds: Creds, db: Db, auth_provider: T) -> Result<impl Reply, Rejection> {
-    let user = db.get_user_by_creds(creds).await.map_err(warp::reject::custom)?;
+#[instrument(skip_all)]
+pub async fn login<S: UserStore + TokenStore, T: AuthProvider>(
+    creds: Creds,
+    db: S,
+    auth_provider: T,
+    lockout: LoginLockout,
+) -> Result<impl Reply, Rejection> {
+    let user = match db.get_user_by_creds(creds, lockout).await {
+        Ok(user) => user,
+        // A locked account is answered like wrong credentials, otherwise the lockout would tell which emails have accounts.
+        Err(ServiceError::AccountLocked(_)) => {
+            METRICS.observe_login("locked");
+            return Err(warp::reject::custom(ServiceError::ObjectNotFound));
+        }
+        Err(e) => {
+            METRICS.observe_login("failure");
+            return Err(warp::reject::custom(e));
+        }
+    };
+    METRICS.observe_login("success");
     let u = UserTknDetails {
-        _id: user._id,
-        is_moderator: user.is_moderator,
+        _id: user._id.clone(),
+        role: user.role,
     };
     let token = auth_provider
         .issue_token(u)
         .ok_or_else(|| warp::reject::custom(ServiceError::AuthTokenEncoderErr))?;
+    let refresh_token = db.issue_refresh_token(user._id, None).await.map_err(warp::reject::custom)?;
 
     Ok(warp::reply::with_status(
-        warp::reply::json(&Token { token }),
+        warp::reply::json(&Token { token, refresh_token }),
         StatusCode::CREATED,
     ))
 }
+
+pub async fn refresh_token<S: TokenStore, T: AuthProvider>(
+    body: RefreshTokenIn,
+    db: S,
+    auth_provider: T,
+) -> Result<impl Reply, Rejection> {
+    let (u, refresh_token) = db
+        .rotate_refresh_token(body.refresh_token)
+        .await
+        .map_err(warp::reject::custom)?;
+    let token = auth_provider
+        .issue_token(u)
+        .ok_or_else(|| warp::reject::custom(ServiceError::AuthTokenEncoderErr))?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&Token { token, refresh_token }),
+        StatusCode::CREATED,
+    ))
+}
+
+pub async fn logout<S: TokenStore>(claims: Claims, db: S, body: LogoutIn) -> Result<impl Reply, Rejection> {
+    db.revoke_token(&claims).await.map_err(warp::reject::custom)?;
+    if let Some(refresh_token) = body.refresh_token {
+        db.revoke_refresh_token_family(refresh_token, claims.sub.clone())
+            .await
+            .map_err(warp::reject::custom)?;
+    }
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
+
+pub async fn revoke_user_sessions<S: TokenStore>(user_id: String, _: UserTknDetails, db: S) -> Result<impl Reply, Rejection> {
+    match db.revoke_user_sessions(user_id).await {
+        Ok(_) => Ok(warp::reply::with_status("", StatusCode::NO_CONTENT)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Always answers the same way, so the response doesn't tell whether the email belongs to an account.
+/// The lookup and the mail happen in the background for the response time not to tell either,
+/// tracked by `tasks` for the shutdown to wait for them.
+pub async fn forgot_password<S: UserStore + AccountTokenStore, M: Mailer>(
+    db: S,
+    mailer: M,
+    tasks: TaskTracker,
+    body: PasswordForgotIn,
+) -> Result<impl Reply, Rejection> {
+    tasks.spawn(async move {
+        let user = match db.get_active_user_by_email(body.email).await {
+            Ok(user) => user,
+            Err(_) => return,
+        };
+        let token = match db
+            .issue_account_token(user._id, AccountTokenPurpose::PasswordReset, None)
+            .await
+        {
+            Ok(token) => token,
+            Err(e) => {
+                event!(Level::ERROR, "Failed to issue password reset token: {}", e);
+                return;
+            }
+        };
+        let mail = Mail {
+            to: user.email,
+            subject: "Reset your password".to_owned(),
+            body: format!(
+                "Use this token to set a new password with POST /password/reset, it is valid for 30 minutes:\n{}\n\nIf you didn't ask for it, just ignore this mail.",
+                token
+            ),
+        };
+        if let Err(e) = mailer.send(mail).await {
+            event!(Level::ERROR, "Failed to send password reset mail: {}", e);
+        }
+    }.in_current_span());
+
+    Ok(warp::reply::with_status("", StatusCode::ACCEPTED))
+}
+
+/// Sets the new password and ends all of the user's sessions, the token can only be used once.
+pub async fn reset_password<S: AccountTokenStore>(db: S, body: PasswordResetIn) -> Result<impl Reply, Rejection> {
+    db.reset_password(body.token, body.new_password)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
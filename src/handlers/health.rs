@@ -0,0 +1,52 @@
+use crate::moderation::{CircuitState, ContentFilter};
+use crate::storage::HealthStore;
+use crate::types::health::{CheckOut, Readiness, ReadinessOut, VersionOut};
+use warp::http::StatusCode;
+use warp::{Rejection, Reply};
+
+pub async fn healthz() -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::with_status("ok", StatusCode::OK))
+}
+
+/// Ready when the database answers, all migrations are applied and the content filter's circuit lets texts through,
+/// answers with `503 Service Unavailable` otherwise and once shutting down.
+/// The remote content filter isn't called, its calls are billed and it is retried behind the circuit anyway.
+pub async fn readyz<S: HealthStore, F: ContentFilter>(db: S, filter: F, readiness: Readiness) -> Result<impl Reply, Rejection> {
+    let mut checks = Vec::new();
+    checks.push(match readiness.is_shutting_down() {
+        false => CheckOut::ok("shutdown"),
+        true => CheckOut::failed("shutdown", "shutting down"),
+    });
+    checks.push(match db.ping().await {
+        Ok(_) => CheckOut::ok("database"),
+        Err(e) => CheckOut::failed("database", e),
+    });
+    checks.push(match db.pending_migrations().await {
+        Ok(pending) if pending.is_empty() => CheckOut::ok("migrations"),
+        Ok(pending) => CheckOut::failed("migrations", format!("{} pending", pending.len())),
+        Err(e) => CheckOut::failed("migrations", e),
+    });
+    // an open circuit only stops texts from getting through when the policy is to reject them
+    let status = filter.status();
+    checks.push(match (status.circuit, status.open_circuit_policy) {
+        (Some(CircuitState::Open), Some("reject")) => CheckOut::failed("content_filter_circuit", "circuit open"),
+        _ => CheckOut::ok("content_filter_circuit"),
+    });
+
+    let ready = checks.iter().all(|check| check.ok);
+    let status = match ready {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    Ok(warp::reply::with_status(
+        warp::reply::json(&ReadinessOut { ready, checks }),
+        status,
+    ))
+}
+
+pub async fn version() -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&VersionOut {
+        version: env!("CARGO_PKG_VERSION"),
+        git_sha: env!("GIT_SHA"),
+    }))
+}
@@ -0,0 +1,80 @@
+use std::str::FromStr;
+use warp::http::StatusCode;
+use warp::{Rejection, Reply};
+
+use crate::moderation::ContentFilter;
+use crate::storage::{MessageStore, QuestionStore};
+use crate::types::message::{MsgIn, MsgType};
+use crate::types::moderation::ReviewSubject;
+use crate::types::question::QuestOut;
+use crate::types::role::Permission;
+use crate::types::shared::Id;
+use crate::types::user::UserTknDetails;
+use error_handling::ServiceError;
+
+/// Censors the content, also telling whether it was accepted unchecked and needs a review.
+pub async fn process_message_text<F: ContentFilter>(mut msg_incoming: MsgIn, filter: F) -> Result<(MsgIn, bool), Rejection> {
+    let checked = filter.check(msg_incoming.content).await.map_err(warp::reject::custom)?;
+    msg_incoming.content = checked.censored_content;
+    Ok((msg_incoming, checked.needs_review))
+}
+
+/// Conversations are only open to the question's author and the staff handling questions.
+fn check_participant(question: &QuestOut, user: &UserTknDetails) -> Result<(), Rejection> {
+    match user.can(Permission::HandleQuestions) || question.author == user._id {
+        true => Ok(()),
+        false => Err(warp::reject::custom(ServiceError::PermissionDenied)),
+    }
+}
+
+pub async fn add_message<S: QuestionStore + MessageStore, F: ContentFilter>(
+    question_id: String,
+    user: UserTknDetails,
+    db: S,
+    filter: F,
+    mut msg: MsgIn,
+) -> Result<impl Reply, Rejection> {
+    let question = db
+        .get_question(Id::from_str(&question_id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+    check_participant(&question, &user)?;
+    let kind = match user.can(Permission::HandleQuestions) {
+        true => MsgType::Response,
+        false => MsgType::Request,
+    };
+    let mut needs_review = false;
+    if !user.can(Permission::BypassContentFilter) {
+        (msg, needs_review) = process_message_text(msg, filter).await?;
+    }
+    let msg = msg.authored_by(user._id, question._id, kind);
+    let inserted_id = db.add_message(msg).await.map_err(warp::reject::custom)?;
+    if needs_review {
+        db.queue_for_review(ReviewSubject::Message(inserted_id.clone()))
+            .await
+            .map_err(warp::reject::custom)?;
+    }
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&inserted_id.as_dict()),
+        StatusCode::CREATED,
+    ))
+}
+
+pub async fn list_messages<S: QuestionStore + MessageStore>(
+    question_id: String,
+    user: UserTknDetails,
+    db: S,
+) -> Result<impl Reply, Rejection> {
+    let question = db
+        .get_question(Id::from_str(&question_id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+    check_participant(&question, &user)?;
+    let messages = db
+        .list_messages(Id::from_str(&question._id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&messages))
+}
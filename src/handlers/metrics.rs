@@ -0,0 +1,103 @@
+use crate::metrics::METRICS;
+use crate::storage::Db;
+use crate::types::question::QuestStatus;
+use error_handling::handle_err;
+use std::time::{Duration, Instant};
+use warp::http::header::{HeaderValue, CONTENT_TYPE};
+use warp::http::Method;
+use warp::reject::MethodNotAllowed;
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+const STANDARD_METHODS: [Method; 9] = [
+    Method::GET,
+    Method::HEAD,
+    Method::POST,
+    Method::PUT,
+    Method::PATCH,
+    Method::DELETE,
+    Method::OPTIONS,
+    Method::CONNECT,
+    Method::TRACE,
+];
+
+/// Static name of the route answering a request, the `route` label of the request metrics.
+#[derive(Debug, Clone, Copy)]
+struct RouteName(&'static str);
+
+fn with_route_name(name: &'static str, mut res: Response) -> Response {
+    res.extensions_mut().insert(RouteName(name));
+    res
+}
+
+/// Names `route` for the request metrics, e.g. `/questions/:id/messages`.
+/// Once the path and method match, the route's rejections are answered right away to keep the name,
+/// the other routes could only have rejected the request too.
+pub fn named<F, R>(name: &'static str, route: F) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    route
+        .map(move |reply: R| with_route_name(name, reply.into_response()))
+        .or_else(move |r: Rejection| async move {
+            if r.is_not_found() || r.find::<MethodNotAllowed>().is_some() {
+                return Err(r);
+            }
+            Ok((with_route_name(name, handle_err(r).await?),))
+        })
+}
+
+/// Counts the response and records its latency, by method, route and status.
+pub fn track_request<R: Reply>(started: Instant, method: Method, reply: R) -> Response {
+    let res = reply.into_response();
+    // extension methods would make for unbounded labels, like unmatched paths do
+    let method = match STANDARD_METHODS.contains(&method) {
+        true => method.as_str(),
+        false => "OTHER",
+    };
+    // requests no route took, like CORS preflights and unknown paths, would make for unbounded labels too
+    let route = res
+        .extensions()
+        .get::<RouteName>()
+        .map(|route| route.0)
+        .unwrap_or("unmatched");
+    METRICS.observe_request(method, route, res.status().as_u16(), started.elapsed());
+    res
+}
+
+/// Refreshes the gauges read from the database and renders all metrics in the Prometheus text format.
+pub async fn metrics(db: Db) -> Result<impl Reply, Rejection> {
+    let pool = &db.connection;
+    let idle = pool.num_idle() as i64;
+    METRICS.db_pool_connections.with_label_values(&["idle"]).set(idle);
+    METRICS
+        .db_pool_connections
+        .with_label_values(&["in_use"])
+        .set(pool.size() as i64 - idle);
+
+    // how long a request would wait for a connection right now
+    let started = Instant::now();
+    let acquired = tokio::time::timeout(Duration::from_secs(5), pool.acquire()).await;
+    METRICS.db_pool_acquire_seconds.set(started.elapsed().as_secs_f64());
+    if let Ok(Ok(conn)) = acquired {
+        drop(conn);
+        if let Ok(counts) = db.count_questions_by_status().await {
+            for status in [
+                QuestStatus::Pending,
+                QuestStatus::Unresolved,
+                QuestStatus::Resolved,
+                QuestStatus::Canceled,
+            ] {
+                let status = status.to_str();
+                let total = counts.iter().find(|(s, _)| *s == status).map(|(_, n)| *n).unwrap_or(0);
+                METRICS.questions.with_label_values(&[status.as_str()]).set(total);
+            }
+        }
+    }
+
+    let mut res = METRICS.render().into_response();
+    res.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain; version=0.0.4"));
+    Ok(res)
+}
@@ -1,7 +1,25 @@
+mod assignment;
 mod auth;
+mod health;
+mod messages;
+mod metrics;
+mod moderation;
+mod profile;
 mod questions;
+mod rate_limit;
+mod request_id;
 mod users;
+mod validation;
 
+pub use assignment::*;
 pub use auth::*;
+pub use health::*;
+pub use messages::*;
+pub use metrics::*;
+pub use moderation::*;
+pub use profile::*;
 pub use questions::*;
+pub use rate_limit::*;
+pub use request_id::*;
 pub use users::*;
+pub use validation::*;
@@ -0,0 +1,25 @@
+use std::str::FromStr;
+use warp::http::StatusCode;
+use warp::{Rejection, Reply};
+
+use crate::moderation::ContentFilter;
+use crate::storage::ReviewStore;
+use crate::types::shared::Id;
+use crate::types::user::UserTknDetails;
+
+pub async fn content_filter_status<F: ContentFilter>(filter: F) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&filter.status()))
+}
+
+pub async fn list_review_queue<S: ReviewStore>(_: UserTknDetails, db: S) -> Result<impl Reply, Rejection> {
+    let items = db.list_review_queue().await.map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&items))
+}
+
+pub async fn mark_reviewed<S: ReviewStore>(id: String, user: UserTknDetails, db: S) -> Result<impl Reply, Rejection> {
+    match db.mark_reviewed(Id::from_str(&id).unwrap(), user._id).await {
+        Ok(_) => Ok(warp::reply::with_status("", StatusCode::NO_CONTENT)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
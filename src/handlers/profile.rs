@@ -0,0 +1,130 @@
+use std::str::FromStr;
+use warp::http::StatusCode;
+use warp::{Rejection, Reply};
+
+use super::users::send_verification_mail;
+use crate::mail::{Mail, Mailer};
+use crate::storage::{AccountTokenStore, TokenStore, UserStore};
+use crate::types::account::{AccountTokenIn, AccountTokenPurpose, EmailChangeIn, PasswordChangeIn, ProfilePatch};
+use crate::types::auth::{Creds, LoginLockout};
+use crate::types::shared::Id;
+use crate::types::user::{UserPatch, UserTknDetails};
+use error_handling::ServiceError;
+
+pub async fn get_me<S: UserStore>(user: UserTknDetails, db: S) -> Result<impl Reply, Rejection> {
+    let me = db
+        .get_user(Id::from_str(&user._id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&me))
+}
+
+pub async fn update_me<S: UserStore>(user: UserTknDetails, db: S, patch: ProfilePatch) -> Result<impl Reply, Rejection> {
+    let patch = UserPatch {
+        email: None,
+        first_name: patch.first_name,
+        last_name: patch.last_name,
+    };
+    db.update_user(Id::from_str(&user._id).unwrap(), patch)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
+
+/// Checks the user's password the way logging in does, failures counting towards the lockout,
+/// so a stolen access token is no way around it.
+async fn check_password<S: UserStore>(db: &S, user_id: &str, password: String, lockout: LoginLockout) -> Result<(), Rejection> {
+    let me = db
+        .get_user(Id::from_str(user_id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+    let creds = Creds {
+        email: me.email,
+        password,
+    };
+    db.get_user_by_creds(creds, lockout).await.map_err(|e| match e {
+        ServiceError::AccountLocked(_) => warp::reject::custom(e),
+        _ => warp::reject::custom(ServiceError::AuthCredsMissing),
+    })?;
+    Ok(())
+}
+
+/// Replaces the password and ends all of the user's sessions, including the current one.
+pub async fn change_password<S: UserStore + TokenStore>(
+    user: UserTknDetails,
+    db: S,
+    lockout: LoginLockout,
+    change: PasswordChangeIn,
+) -> Result<impl Reply, Rejection> {
+    check_password(&db, &user._id, change.current_password.clone(), lockout).await?;
+    db.change_password(Id::from_str(&user._id).unwrap(), change.current_password, change.new_password)
+        .await
+        .map_err(warp::reject::custom)?;
+    db.revoke_user_sessions(user._id).await.map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
+
+/// Mails a confirmation token to the new address, the email only changes once it comes back.
+/// Emails of other accounts are refused upfront, before anything gets mailed.
+pub async fn request_email_change<S: UserStore + AccountTokenStore, M: Mailer>(
+    user: UserTknDetails,
+    db: S,
+    mailer: M,
+    lockout: LoginLockout,
+    change: EmailChangeIn,
+) -> Result<impl Reply, Rejection> {
+    check_password(&db, &user._id, change.password, lockout).await?;
+    if db.is_email_taken(change.email.clone()).await.map_err(warp::reject::custom)? {
+        return Err(warp::reject::custom(ServiceError::ConflictInDb));
+    }
+    let token = db
+        .issue_account_token(user._id, AccountTokenPurpose::EmailChange, Some(change.email.clone()))
+        .await
+        .map_err(warp::reject::custom)?;
+    let mail = Mail {
+        to: change.email,
+        subject: "Confirm your new email".to_owned(),
+        body: format!(
+            "Use this token to confirm your new email with POST /me/email/confirm, it is valid for 24 hours:\n{}",
+            token
+        ),
+    };
+    mailer.send(mail).await.map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::ACCEPTED))
+}
+
+pub async fn confirm_email_change<S: AccountTokenStore>(
+    user: UserTknDetails,
+    db: S,
+    body: AccountTokenIn,
+) -> Result<impl Reply, Rejection> {
+    db.confirm_email_change(body.token, user._id)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
+
+pub async fn resend_verification<S: UserStore + AccountTokenStore, M: Mailer>(
+    user: UserTknDetails,
+    db: S,
+    mailer: M,
+    public_url: String,
+) -> Result<impl Reply, Rejection> {
+    let me = db
+        .get_user(Id::from_str(&user._id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+    if me.email_verified_at.is_some() {
+        return Ok(warp::reply::with_status("", StatusCode::NO_CONTENT));
+    }
+    send_verification_mail(&db, &mailer, &public_url, me._id, me.email)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::ACCEPTED))
+}
@@ -1,44 +1,137 @@
 use std::str::FromStr;
+use warp::http::header::{HeaderValue, LINK};
 use warp::http::StatusCode;
 use warp::{Rejection, Reply};
 
-use crate::aux::filter_out_bad_words;
-use crate::storage::Db;
-use crate::types::pagination::Pagination;
-use crate::types::question::QuestIn;
+use crate::moderation::ContentFilter;
+use crate::storage::{QuestionStore, UserStore};
+use crate::types::account::VerificationPolicy;
+use crate::types::assignment::AssignmentStrategy;
+use crate::types::moderation::ReviewSubject;
+use crate::types::query::QuestQuery;
+use crate::types::question::{QuestIn, QuestStatus, StatusActor, StatusChangeIn};
+use crate::types::role::Permission;
 use crate::types::shared::Id;
 use crate::types::user::UserTknDetails;
+use error_handling::ServiceError;
+use tracing::{event, Instrument, Level};
 
 type Params = std::collections::HashMap<String, String>;
 
-pub async fn process_question_text(mut quest_incoming: QuestIn) -> Result<QuestIn, Rejection> {
-    let title = tokio::spawn(filter_out_bad_words(quest_incoming.title));
-    let content = tokio::spawn(filter_out_bad_words(quest_incoming.content));
+/// Censors the title and the content, also telling whether either of them was accepted unchecked and needs a review.
+pub async fn process_question_text<F: ContentFilter>(
+    mut quest_incoming: QuestIn,
+    filter: F,
+) -> Result<(QuestIn, bool), Rejection> {
+    let title_filter = filter.clone();
+    let title = tokio::spawn(async move { title_filter.check(quest_incoming.title).await }.in_current_span());
+    let content = tokio::spawn(async move { filter.check(quest_incoming.content).await }.in_current_span());
     let (title, content) = (title.await.unwrap(), content.await.unwrap());
-    quest_incoming.title = title.map_err(warp::reject::custom)?;
-    quest_incoming.content = content.map_err(warp::reject::custom)?;
-    Ok(quest_incoming)
+    let title = title.map_err(warp::reject::custom)?;
+    let content = content.map_err(warp::reject::custom)?;
+    quest_incoming.title = title.censored_content;
+    quest_incoming.content = content.censored_content;
+    Ok((quest_incoming, title.needs_review || content.needs_review))
 }
 
-pub async fn list_guestions(query_string_params: Params, db: Db) -> Result<impl Reply, Rejection> {
-    let pagination = match query_string_params.is_empty() {
-        true => Pagination::default(),
-        false => Pagination::parse_from_map(query_string_params)?,
+/// RFC 8288 `Link` header value pointing at the neighbouring pages, keeping all the other query parameters.
+fn page_links(params: &Params, next: &Option<String>, prev: &Option<String>) -> Option<String> {
+    let link = |rel: &str, cursor: &String| {
+        let mut link_params: Vec<(&str, &str)> = params
+            .iter()
+            .filter(|(key, _)| !["after", "before"].contains(&key.as_str()))
+            .map(|(key, val)| (key.as_str(), val.as_str()))
+            .collect();
+        link_params.sort();
+        link_params.push((if rel == "next" { "after" } else { "before" }, cursor));
+        let query_string = serde_urlencoded::to_string(link_params).unwrap_or_default();
+        format!("</questions?{}>; rel=\"{}\"", query_string, rel)
+    };
+    let links: Vec<String> = [("next", next), ("prev", prev)]
+        .into_iter()
+        .filter_map(|(rel, cursor)| cursor.as_ref().map(|cursor| link(rel, cursor)))
+        .collect();
+    match links.is_empty() {
+        true => None,
+        false => Some(links.join(", ")),
+    }
+}
+
+pub async fn list_guestions<S: QuestionStore>(query_string_params: Params, db: S) -> Result<impl Reply, Rejection> {
+    let query = QuestQuery::parse_from_map(query_string_params.clone())?;
+    let total = match query.with_total {
+        true => Some(db.count_questions(&query).await.map_err(warp::reject::custom)?),
+        false => None,
     };
-    let questions = db
-        .list_questions(pagination.offset, pagination.limit)
-        .await
-        .map_err(warp::reject::custom)?;
 
-    Ok(warp::reply::json(&questions))
+    let mut resp = match (&query.keyset, &query.search) {
+        (Some(keyset), _) => {
+            let mut page = db.list_questions_page(&query, keyset).await.map_err(warp::reject::custom)?;
+            page.total = total;
+            let links = page_links(&query_string_params, &page.next, &page.prev);
+            let mut resp = warp::reply::json(&page).into_response();
+            if let Some(links) = links.and_then(|links| HeaderValue::from_str(&links).ok()) {
+                resp.headers_mut().insert(LINK, links);
+            }
+            resp
+        }
+        (None, Some(_)) => {
+            let hits = db.search_questions(&query).await.map_err(warp::reject::custom)?;
+            warp::reply::json(&hits).into_response()
+        }
+        (None, None) => {
+            let questions = db.list_questions(&query).await.map_err(warp::reject::custom)?;
+            warp::reply::json(&questions).into_response()
+        }
+    };
+    if let Some(total) = total {
+        resp.headers_mut().insert("X-Total-Count", HeaderValue::from(total));
+    }
+
+    Ok(resp)
 }
 
-pub async fn add_question(user: UserTknDetails, db: Db, mut question: QuestIn) -> Result<impl Reply, Rejection> {
-    if !user.is_moderator {
-        question = process_question_text(question).await?;
+pub async fn add_question<S: QuestionStore + UserStore, F: ContentFilter>(
+    user: UserTknDetails,
+    db: S,
+    filter: F,
+    strategy: AssignmentStrategy,
+    verification: VerificationPolicy,
+    mut question: QuestIn,
+) -> Result<impl Reply, Rejection> {
+    if verification == VerificationPolicy::Required {
+        let author = db
+            .get_user(Id::from_str(&user._id).unwrap())
+            .await
+            .map_err(warp::reject::custom)?;
+        if author.email_verified_at.is_none() {
+            return Err(warp::reject::custom(ServiceError::EmailNotVerified));
+        }
+    }
+    let mut needs_review = false;
+    if !user.can(Permission::BypassContentFilter) {
+        (question, needs_review) = process_question_text(question, filter).await?;
+    }
+    if !user.can(Permission::HandleQuestions) {
+        question.status = Some(QuestStatus::Pending);
     }
     let question = question.authored_by(user._id);
     let inserted_id = db.add_question(question).await.map_err(warp::reject::custom)?;
+    if needs_review {
+        db.queue_for_review(ReviewSubject::Question(inserted_id.clone()))
+            .await
+            .map_err(warp::reject::custom)?;
+    }
+    // the question is already saved, so failing to auto-assign it only gets logged
+    match db.pick_assignee(strategy).await {
+        Ok(Some(staff_id)) => {
+            if let Err(e) = db.assign_question(inserted_id.clone(), None, Some(staff_id)).await {
+                event!(Level::WARN, "Failed to auto-assign question {}: {}", inserted_id.to_str(), e);
+            }
+        }
+        Ok(None) => (),
+        Err(e) => event!(Level::WARN, "Failed to pick an assignee: {}", e),
+    }
 
     Ok(warp::reply::with_status(
         warp::reply::json(&inserted_id.as_dict()),
@@ -46,31 +139,91 @@ pub async fn add_question(user: UserTknDetails, db: Db, mut question: QuestIn) -
     ))
 }
 
-pub async fn update_question(user: UserTknDetails, id: String, db: Db, mut question: QuestIn) -> Result<impl Reply, Rejection> {
-    if !user.is_moderator {
-        question = process_question_text(question).await?;
+pub async fn update_question<S: QuestionStore, F: ContentFilter>(
+    id: String,
+    user: UserTknDetails,
+    db: S,
+    filter: F,
+    mut question: QuestIn,
+) -> Result<impl Reply, Rejection> {
+    let current = db
+        .get_question(Id::from_str(&id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+    let actor = StatusActor::of(&user._id, user.can(Permission::HandleQuestions), &current);
+    let status_change = question
+        .status
+        .filter(|status| *status != current.status)
+        .map(|status| (current.status, status));
+    if let Some((from, to)) = status_change {
+        from.check_transition(to, actor)?;
     }
-    let question = question.authored_by(user._id);
-    match db
-        .update_question(Id::from_str(&id).unwrap(), question, user.is_moderator)
+    let mut needs_review = false;
+    if !user.can(Permission::BypassContentFilter) {
+        (question, needs_review) = process_question_text(question, filter).await?;
+    }
+    let question = question.authored_by(user._id.clone());
+    db.update_question(
+        Id::from_str(&id).unwrap(),
+        question,
+        user.can(Permission::EditAnyQuestion),
+        status_change,
+    )
+    .await
+    .map_err(warp::reject::custom)?;
+    if needs_review {
+        db.queue_for_review(ReviewSubject::Question(Id::from_str(&id).unwrap()))
+            .await
+            .map_err(warp::reject::custom)?;
+    }
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
+
+pub async fn change_question_status<S: QuestionStore>(
+    id: String,
+    user: UserTknDetails,
+    db: S,
+    change: StatusChangeIn,
+) -> Result<impl Reply, Rejection> {
+    let current = db
+        .get_question(Id::from_str(&id).unwrap())
         .await
-    {
-        Ok(_) => Ok(warp::reply::with_status("", StatusCode::NO_CONTENT)),
-        Err(e) => Err(warp::reject::custom(e)),
+        .map_err(warp::reject::custom)?;
+    let actor = StatusActor::of(&user._id, user.can(Permission::HandleQuestions), &current);
+    if actor == StatusActor::Other {
+        return Err(warp::reject::custom(ServiceError::PermissionDenied));
     }
+    current.status.check_transition(change.status, actor)?;
+    db.change_question_status(Id::from_str(&id).unwrap(), current.status, change.status, user._id)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
 }
 
-pub async fn delete_question(user: UserTknDetails, id: String, db: Db) -> Result<impl Reply, Rejection> {
-    match db
-        .delete_question(Id::from_str(&id).unwrap(), user._id, user.is_moderator)
+pub async fn list_status_history<S: QuestionStore>(id: String, db: S) -> Result<impl Reply, Rejection> {
+    let question = db
+        .get_question(Id::from_str(&id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+    let history = db
+        .list_status_history(Id::from_str(&question._id).unwrap())
         .await
-    {
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&history))
+}
+
+pub async fn delete_question<S: QuestionStore>(id: String, user: UserTknDetails, db: S) -> Result<impl Reply, Rejection> {
+    let force = user.can(Permission::DeleteAnyQuestion);
+    match db.delete_question(Id::from_str(&id).unwrap(), user._id, force).await {
         Ok(_) => Ok(warp::reply::with_status("", StatusCode::NO_CONTENT)),
         Err(e) => Err(warp::reject::custom(e)),
     }
 }
 
-pub async fn get_question(id: String, db: Db) -> Result<impl Reply, Rejection> {
+pub async fn get_question<S: QuestionStore>(id: String, db: S) -> Result<impl Reply, Rejection> {
     let question = db
         .get_question(Id::from_str(&id).unwrap())
         .await
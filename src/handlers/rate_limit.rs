@@ -0,0 +1,97 @@
+use crate::ratelimit::{Quota, RateLimitStore};
+use crate::types::user::UserTknDetails;
+use std::net::{IpAddr, SocketAddr};
+use warp::{Filter, Rejection};
+
+/// The client's IP: the peer address, or when the peer is one of the `trusted_proxies`,
+/// the last `X-Forwarded-For` hop that isn't one of them.
+pub fn client_ip(trusted_proxies: Vec<IpAddr>) -> impl Filter<Extract = (Option<IpAddr>,), Error = Rejection> + Clone {
+    warp::addr::remote()
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .map(move |addr: Option<SocketAddr>, forwarded_for: Option<String>| {
+            addr.map(|addr| forwarded_client(addr.ip(), forwarded_for.as_deref(), &trusted_proxies))
+        })
+}
+
+/// Walks `X-Forwarded-For` from the right, each hop being added by the one after it, and stops at the first
+/// hop not added by a trusted proxy. Entries further left are whatever the client sent.
+fn forwarded_client(peer: IpAddr, forwarded_for: Option<&str>, trusted_proxies: &[IpAddr]) -> IpAddr {
+    let mut client = peer;
+    for hop in forwarded_for.unwrap_or_default().rsplit(',') {
+        if !trusted_proxies.contains(&client) {
+            break;
+        }
+        match hop.trim().parse() {
+            Ok(ip) => client = ip,
+            Err(_) => break,
+        }
+    }
+    client
+}
+
+/// Takes a token from the bucket for `scope` of the IP found by `client`, rejecting with `429 Too Many Requests`
+/// once it's empty. A `None` quota lets everything through.
+pub fn rate_limit_ip<L: RateLimitStore>(
+    client: impl Filter<Extract = (Option<IpAddr>,), Error = Rejection> + Clone,
+    limiter: L,
+    scope: &'static str,
+    quota: Option<Quota>,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    client
+        .and_then(move |ip: Option<IpAddr>| {
+            let limiter = limiter.clone();
+            async move {
+                if let (Some(quota), Some(ip)) = (quota, ip) {
+                    let key = format!("{}:ip:{}", scope, ip);
+                    limiter.acquire(key, quota).await.map_err(warp::reject::custom)?;
+                }
+                Ok::<_, Rejection>(())
+            }
+        })
+        .untuple_one()
+}
+
+/// Like `rate_limit_ip`, but with a bucket per user authenticated by `user`.
+pub fn rate_limit_user<L: RateLimitStore>(
+    user: impl Filter<Extract = (UserTknDetails,), Error = Rejection> + Clone,
+    limiter: L,
+    scope: &'static str,
+    quota: Option<Quota>,
+) -> impl Filter<Extract = (UserTknDetails,), Error = Rejection> + Clone {
+    user.and_then(move |user: UserTknDetails| {
+        let limiter = limiter.clone();
+        async move {
+            if let Some(quota) = quota {
+                let key = format!("{}:user:{}", scope, user._id);
+                limiter.acquire(key, quota).await.map_err(warp::reject::custom)?;
+            }
+            Ok::<_, Rejection>(user)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_from_untrusted_peers() {
+        let client = forwarded_client(ip("203.0.113.7"), Some("198.51.100.1"), &[ip("10.0.0.1")]);
+        assert_eq!(client, ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn forwarded_for_is_followed_through_trusted_proxies_only() {
+        let trusted = [ip("10.0.0.1"), ip("10.0.0.2")];
+        assert_eq!(
+            forwarded_client(ip("10.0.0.1"), Some("198.51.100.1, 203.0.113.7, 10.0.0.2"), &trusted),
+            ip("203.0.113.7")
+        );
+        assert_eq!(forwarded_client(ip("10.0.0.1"), None, &trusted), ip("10.0.0.1"));
+        assert_eq!(forwarded_client(ip("10.0.0.1"), Some("not-an-ip"), &trusted), ip("10.0.0.1"));
+    }
+}
@@ -0,0 +1,37 @@
+use error_handling::REQUEST_ID_HEADER;
+use std::convert::Infallible;
+use tracing::field::{display, Empty};
+use tracing::Span;
+use uuid::Uuid;
+use warp::{http::HeaderMap, trace::Info, Filter};
+
+/// Takes the caller's `X-Request-Id` when it's usable, otherwise generates one,
+/// and records it on the request span for every log line of the request to carry it.
+pub fn request_id() -> impl Filter<Extract = (String,), Error = Infallible> + Clone {
+    warp::header::headers_cloned().map(|headers: HeaderMap| {
+        let id = headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|id| id.to_str().ok())
+            .filter(|id| !id.is_empty() && id.len() <= 128 && id.chars().all(|c| c.is_ascii_graphic()))
+            .map(str::to_owned)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        Span::current().record("request_id", id.as_str());
+        id
+    })
+}
+
+/// Span wrapping the whole request, like `warp::trace::request()` plus the `request_id` field.
+pub fn request_span(info: Info) -> Span {
+    let span = tracing::info_span!(
+        "request",
+        method = %info.method(),
+        path = %info.path(),
+        version = ?info.version(),
+        remote.addr = Empty,
+        request_id = Empty,
+    );
+    if let Some(addr) = info.remote_addr() {
+        span.record("remote.addr", display(addr));
+    }
+    span
+}
@@ -1,7 +1,43 @@
-use crate::{storage::Db, types::user::UserIn};
+use crate::{
+    mail::{Mail, Mailer},
+    storage::{AccountTokenStore, TokenStore, UserStore},
+    types::{
+        account::{AccountTokenIn, AccountTokenPurpose},
+        pagination::Pagination,
+        role::{Role, RoleIn, RoleOut},
+        shared::Id,
+        user::{UserIn, UserPatch, UserTknDetails},
+    },
+};
 use error_handling::ServiceError;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::{event, Level};
 use warp::{http::StatusCode, Rejection, Reply};
 
+/// Issues an email verification token and mails the link to `email`.
+pub async fn send_verification_mail<S: AccountTokenStore, M: Mailer>(
+    db: &S,
+    mailer: &M,
+    public_url: &str,
+    user_id: String,
+    email: String,
+) -> Result<(), ServiceError> {
+    let token = db
+        .issue_account_token(user_id, AccountTokenPurpose::EmailVerification, None)
+        .await?;
+    let mail = Mail {
+        to: email,
+        subject: "Verify your email".to_owned(),
+        body: format!(
+            "Open this link to verify your email, it is valid for 48 hours:\n{}/users/verify?token={}",
+            public_url.trim_end_matches('/'),
+            token
+        ),
+    };
+    mailer.send(mail).await
+}
+
 pub async fn validate_moderator(
     new_user: UserIn,
     moderator_key_presented: Option<String>,
@@ -19,19 +55,146 @@ pub async fn validate_moderator(
     Ok(new_user)
 }
 
-pub async fn add_user(
+pub async fn add_user<S: UserStore + AccountTokenStore, M: Mailer>(
     new_user: UserIn,
     auth_headers: Option<String>,
-    db: Db,
+    db: S,
     moderator_key: String,
+    mailer: M,
+    public_url: String,
 ) -> Result<impl Reply, Rejection> {
     let new_user = validate_moderator(new_user, auth_headers, moderator_key)
         .await
         .map_err(warp::reject::custom)?;
+    let email = new_user.email.clone();
     let inserted_id = db.add_user(new_user).await.map_err(warp::reject::custom)?;
+    // the account exists either way, a lost mail can be sent again with `POST /me/verification`
+    if let Err(e) = send_verification_mail(&db, &mailer, &public_url, inserted_id.to_str(), email).await {
+        event!(Level::WARN, "Failed to send verification mail: {}", e);
+    }
 
     Ok(warp::reply::with_status(
         warp::reply::json(&inserted_id.as_dict()),
         StatusCode::CREATED,
     ))
 }
+
+pub async fn verify_email<S: UserStore + AccountTokenStore>(query: AccountTokenIn, db: S) -> Result<impl Reply, Rejection> {
+    let token = db
+        .consume_account_token(query.token, AccountTokenPurpose::EmailVerification)
+        .await
+        .map_err(warp::reject::custom)?;
+    db.mark_email_verified(Id::from_str(&token.user_id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("Email verified", StatusCode::OK))
+}
+
+pub async fn list_roles() -> Result<impl Reply, Rejection> {
+    let roles: Vec<RoleOut> = Role::ALL
+        .into_iter()
+        .map(|role| RoleOut {
+            role,
+            permissions: role.permissions(),
+        })
+        .collect();
+
+    Ok(warp::reply::json(&roles))
+}
+
+/// Changes the user's role and ends their sessions, so tokens carrying the old role stop working.
+pub async fn set_user_role<S: UserStore + TokenStore>(
+    user_id: String,
+    user: UserTknDetails,
+    db: S,
+    role: RoleIn,
+) -> Result<impl Reply, Rejection> {
+    if user._id == user_id {
+        return Err(warp::reject::custom(ServiceError::InvalidParam(
+            "admins cannot change their own role".to_owned(),
+        )));
+    }
+    db.set_user_role(Id::from_str(&user_id).unwrap(), role.role)
+        .await
+        .map_err(warp::reject::custom)?;
+    db.revoke_user_sessions(user_id).await.map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
+
+pub async fn list_users<S: UserStore>(
+    _: UserTknDetails,
+    params: HashMap<String, String>,
+    db: S,
+) -> Result<impl Reply, Rejection> {
+    let pagination = match params.is_empty() {
+        true => Pagination::default(),
+        false => Pagination::parse_from_map(params).map_err(warp::reject::custom)?,
+    };
+    let users = db.list_users(&pagination).await.map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&users))
+}
+
+pub async fn get_user<S: UserStore>(user_id: String, _: UserTknDetails, db: S) -> Result<impl Reply, Rejection> {
+    let user = db
+        .get_user(Id::from_str(&user_id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&user))
+}
+
+pub async fn update_user<S: UserStore>(
+    user_id: String,
+    _: UserTknDetails,
+    db: S,
+    patch: UserPatch,
+) -> Result<impl Reply, Rejection> {
+    db.update_user(Id::from_str(&user_id).unwrap(), patch)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
+
+/// Blocks the user from logging in and ends their sessions, until reactivated.
+pub async fn deactivate_user<S: UserStore + TokenStore>(
+    user_id: String,
+    user: UserTknDetails,
+    db: S,
+) -> Result<impl Reply, Rejection> {
+    if user._id == user_id {
+        return Err(warp::reject::custom(ServiceError::InvalidParam(
+            "admins cannot deactivate themselves".to_owned(),
+        )));
+    }
+    db.set_user_active(Id::from_str(&user_id).unwrap(), false)
+        .await
+        .map_err(warp::reject::custom)?;
+    db.revoke_user_sessions(user_id).await.map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
+
+pub async fn reactivate_user<S: UserStore>(user_id: String, _: UserTknDetails, db: S) -> Result<impl Reply, Rejection> {
+    db.set_user_active(Id::from_str(&user_id).unwrap(), true)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
+
+pub async fn delete_user<S: UserStore>(user_id: String, user: UserTknDetails, db: S) -> Result<impl Reply, Rejection> {
+    if user._id == user_id {
+        return Err(warp::reject::custom(ServiceError::InvalidParam(
+            "admins cannot delete themselves".to_owned(),
+        )));
+    }
+    db.delete_user(Id::from_str(&user_id).unwrap())
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
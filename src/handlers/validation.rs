@@ -0,0 +1,35 @@
+use serde::de::DeserializeOwned;
+use validator::Validate;
+use warp::{Filter, Rejection};
+
+use crate::types::validation::validation_failed;
+
+/// Upper bound on request bodies, comfortably above the largest valid question.
+pub const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+/// Like `warp::body::json`, but also runs the body's validation rules and rejects with the fields that failed.
+pub fn json_body<T: DeserializeOwned + Validate + Send>() -> impl Filter<Extract = (T,), Error = Rejection> + Clone {
+    warp::body::content_length_limit(MAX_BODY_BYTES)
+        .and(warp::body::json())
+        .and_then(|body: T| async move {
+            match body.validate() {
+                Ok(_) => Ok(body),
+                Err(errors) => Err(warp::reject::custom(validation_failed(errors))),
+            }
+        })
+}
+
+/// Like `json_body`, but a request without a body stands for the default value.
+pub fn optional_json_body<T: DeserializeOwned + Validate + Default + Send>(
+) -> impl Filter<Extract = (T,), Error = Rejection> + Clone {
+    warp::header::optional::<u64>("content-length")
+        .and(warp::header::optional::<String>("transfer-encoding"))
+        .and_then(|length: Option<u64>, encoding: Option<String>| async move {
+            match (length, encoding) {
+                (Some(0), _) | (None, None) => Ok(T::default()),
+                _ => Err(warp::reject::not_found()),
+            }
+        })
+        .or(json_body())
+        .unify()
+}
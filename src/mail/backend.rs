@@ -0,0 +1,66 @@
+use super::base::{Mail, Mailer};
+use super::file::FileMailer;
+use super::logging::LogMailer;
+use super::smtp::{SmtpMailer, SmtpSettings};
+use error_handling::ServiceError;
+use std::path::PathBuf;
+
+/// Mail sender chosen with the `mail.backend` setting:
+/// `log` (default) only writes mails to the log, `file` drops them into `mail.dir`, `smtp` actually sends them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MailerKind {
+    #[default]
+    Log,
+    File,
+    Smtp,
+}
+
+impl std::str::FromStr for MailerKind {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "log" => Ok(Self::Log),
+            "file" => Ok(Self::File),
+            "smtp" => Ok(Self::Smtp),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "expected log, file or smtp",
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum MailSettings {
+    Log,
+    File(PathBuf),
+    Smtp(SmtpSettings),
+}
+
+#[derive(Clone, Debug)]
+pub enum MailerBackend {
+    Smtp(Box<SmtpMailer>),
+    File(FileMailer),
+    Log(LogMailer),
+}
+
+impl MailerBackend {
+    pub fn new(settings: MailSettings) -> Result<Self, std::io::Error> {
+        match settings {
+            MailSettings::Log => Ok(Self::Log(LogMailer)),
+            MailSettings::File(dir) => Ok(Self::File(FileMailer::new(dir)?)),
+            MailSettings::Smtp(smtp) => Ok(Self::Smtp(Box::new(SmtpMailer::new(smtp)?))),
+        }
+    }
+}
+
+impl Mailer for MailerBackend {
+    async fn send(&self, mail: Mail) -> Result<(), ServiceError> {
+        match self {
+            Self::Smtp(mailer) => mailer.send(mail).await,
+            Self::File(mailer) => mailer.send(mail).await,
+            Self::Log(mailer) => mailer.send(mail).await,
+        }
+    }
+}
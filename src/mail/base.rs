@@ -0,0 +1,13 @@
+use error_handling::ServiceError;
+use std::future::Future;
+
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+pub trait Mailer: std::fmt::Debug + Clone + Send + Sync + 'static {
+    fn send(&self, mail: Mail) -> impl Future<Output = Result<(), ServiceError>> + Send;
+}
@@ -0,0 +1,31 @@
+use chrono::Utc;
+use error_handling::ServiceError;
+use std::path::PathBuf;
+use tracing::{event, Level};
+use uuid::Uuid;
+
+use super::base::{Mail, Mailer};
+
+/// Drops every mail into its own file under `mail.dir`, for development and tests to read them back.
+#[derive(Clone, Debug)]
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: PathBuf) -> Result<Self, std::io::Error> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(FileMailer { dir })
+    }
+}
+
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> Result<(), ServiceError> {
+        let file_name = format!("{}-{}.eml", Utc::now().format("%Y%m%dT%H%M%S%.6f"), Uuid::new_v4().simple());
+        let contents = format!("To: {}\nSubject: {}\n\n{}\n", mail.to, mail.subject, mail.body);
+        tokio::fs::write(self.dir.join(file_name), contents).await.map_err(|e| {
+            event!(Level::ERROR, "Failed to write mail to {}: {}", self.dir.display(), e);
+            ServiceError::ExternalApiError
+        })
+    }
+}
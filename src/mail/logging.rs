@@ -0,0 +1,15 @@
+use error_handling::ServiceError;
+use tracing::{event, Level};
+
+use super::base::{Mail, Mailer};
+
+/// Writes mails to the log instead of sending them, for local development.
+#[derive(Debug, Clone, Default)]
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    async fn send(&self, mail: Mail) -> Result<(), ServiceError> {
+        event!(Level::INFO, "Mail to {}, subject: {}\n{}", mail.to, mail.subject, mail.body);
+        Ok(())
+    }
+}
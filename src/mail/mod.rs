@@ -0,0 +1,9 @@
+mod backend;
+mod base;
+mod file;
+mod logging;
+mod smtp;
+
+pub use backend::*;
+pub use base::*;
+pub use smtp::SmtpSettings;
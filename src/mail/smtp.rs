@@ -0,0 +1,67 @@
+use error_handling::ServiceError;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use tracing::{event, Level};
+
+use super::base::{Mail, Mailer};
+
+#[derive(Debug, Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: Option<u16>,
+    pub credentials: Option<(String, String)>,
+    pub from: Mailbox,
+}
+
+/// Sends mails through an SMTP relay using STARTTLS.
+#[derive(Clone, Debug)]
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(settings: SmtpSettings) -> Result<Self, std::io::Error> {
+        let mut transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.host).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid SMTP host {}: {}", settings.host, e),
+            )
+        })?;
+        if let Some(port) = settings.port {
+            transport = transport.port(port);
+        }
+        if let Some((username, password)) = settings.credentials {
+            transport = transport.credentials(Credentials::new(username, password));
+        }
+
+        Ok(SmtpMailer {
+            transport: transport.build(),
+            from: settings.from,
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> Result<(), ServiceError> {
+        let to = mail.to.parse::<Mailbox>().map_err(|e| {
+            event!(Level::WARN, "Invalid recipient {}: {}", mail.to, e);
+            ServiceError::InvalidParam(format!("invalid email '{}'", mail.to))
+        })?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(mail.subject)
+            .body(mail.body)
+            .map_err(|e| {
+                event!(Level::ERROR, "Failed to build mail: {}", e);
+                ServiceError::ExternalApiError
+            })?;
+        self.transport.send(message).await.map_err(|e| {
+            event!(Level::ERROR, "Failed to send mail: {}", e);
+            ServiceError::ExternalApiError
+        })?;
+        Ok(())
+    }
+}
@@ -1,100 +1,159 @@
 use auth::JWTAuth as AuthTokenIssuer;
+use config::{Config, LogFormat};
 use error_handling::handle_err;
-use storage::Db;
+use mail::MailerBackend;
+use metrics::METRICS;
+use moderation::ContentFilterBackend;
+use ratelimit::RateLimitBackend;
+use routes::{RouteSettings, Services};
+use storage::{Db, UserStore};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::oneshot;
+use tokio_util::task::TaskTracker;
+use tracing::{event, Level};
 use tracing_subscriber::fmt::format::FmtSpan;
-use warp::{http, Filter};
+use types::health::Readiness;
+use warp::Filter;
 
 mod auth;
-mod aux;
+mod config;
 mod handlers;
+mod mail;
+mod metrics;
+mod moderation;
+mod ratelimit;
+mod routes;
 mod storage;
 mod types;
 
 #[tokio::main]
 async fn main() {
-    let log_filter = std::env::var("RUST_LOG").unwrap_or_else(|_| "customer_care=warn,warp=error".to_owned());
-
-    tracing_subscriber::fmt()
-        .with_env_filter(log_filter)
-        .with_span_events(FmtSpan::CLOSE)
-        .init();
-
-    let cors = warp::cors()
-        .allow_methods(vec![http::Method::PUT, http::Method::DELETE])
-        .allow_origins(vec!["http://front-end-service:3000"])
-        .allow_header("content-type");
-
-    let token_issuer = AuthTokenIssuer::new().expect("Failed to instantiate auth tokens issuer");
-    let token_checker = token_issuer.clone();
-
-    let db = Db::from_env().await;
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(errors) => {
+            eprintln!("Invalid configuration:");
+            for err in errors {
+                eprintln!("  {}", err);
+            }
+            std::process::exit(2);
+        }
+    };
+
+    let logs = tracing_subscriber::fmt()
+        .with_env_filter(config.log_filter.as_str())
+        .with_span_events(FmtSpan::CLOSE);
+    match config.log_format {
+        LogFormat::Text => logs.init(),
+        // one object per line, with the fields of the enclosing spans (e.g. the request id) on every event
+        LogFormat::Json => logs.json().with_current_span(true).with_span_list(true).init(),
+    }
+
+    let db = Db::from_settings(&config.database).await;
     db.run_migrations().await;
-    let db_filter = warp::any().map(move || db.clone());
-
-    let moderator_key = std::env::var("MODERATOR_AUTH_KEY").expect("MODERATOR_AUTH_KEY");
-
-    let add_usr_route = warp::path!("users")
-        .and(warp::post())
-        .and(warp::body::json())
-        .and(handlers::parse_auth_headers())
-        .and(db_filter.clone())
-        .and(warp::any().map(move || moderator_key.clone()))
-        .and_then(handlers::add_user);
-
-    let login_user_route = warp::path!("login")
-        .and(warp::post())
-        .and(warp::body::json())
-        .and(db_filter.clone())
-        .and(warp::any().map(move || token_issuer.clone()))
-        .and_then(handlers::login);
-
-    let list_questions_route = warp::path!("questions")
+    if let Some(email) = config.auth.bootstrap_admin_email.clone() {
+        match db.bootstrap_admin(email.clone()).await {
+            Ok(true) => event!(Level::WARN, "Made {} an admin, unset auth.bootstrap_admin_email now", email),
+            Ok(false) => event!(
+                Level::INFO,
+                "Admin bootstrap skipped, there is an admin already or no active user with email {}",
+                email
+            ),
+            Err(e) => event!(Level::ERROR, "Admin bootstrap failed: {}", e),
+        }
+    }
+    METRICS.db_pool_max_connections.set(config.database.max_connections as i64);
+    let db_conn = db.clone();
+    let db_filter = warp::any().map(move || db_conn.clone());
+
+    let tasks = TaskTracker::new();
+    let readiness = Readiness::default();
+    let services = Services {
+        db: db.clone(),
+        auth: AuthTokenIssuer::new(config.auth.secret.clone()),
+        content_filter: ContentFilterBackend::new(config.content_filter).expect("Failed to instantiate content filter"),
+        mailer: MailerBackend::new(config.mail).expect("Failed to instantiate mailer"),
+        rate_limiter: RateLimitBackend::new(config.rate_limit.store, db.clone()),
+        tasks: tasks.clone(),
+        readiness: readiness.clone(),
+    };
+    let settings = RouteSettings {
+        cors_origins: config.server.cors_origins.clone(),
+        public_url: config.server.public_url.clone(),
+        moderator_key: config.auth.moderator_key.clone(),
+        lockout: config.auth.lockout,
+        email_verification: config.auth.email_verification,
+        auto_assign: config.auto_assign,
+        rate_limits: config.rate_limit.limits,
+        trusted_proxies: config.rate_limit.trusted_proxies.clone(),
+    };
+    let routes = routes::api(services, settings);
+
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let (addr, server) = warp::serve(routes).bind_with_graceful_shutdown(config.server.addr, async move {
+        stop_rx.await.ok();
+    });
+    event!(Level::INFO, "Listening on {}", addr);
+    let mut server = tokio::spawn(server);
+
+    // on a listener of its own, to be kept off the public network
+    let metrics_route = warp::path!("metrics")
         .and(warp::get())
-        .and(warp::query())
         .and(db_filter.clone())
-        .and_then(handlers::list_guestions);
-
-    let add_question_route = warp::path!("questions")
-        .and(warp::post())
-        .and(handlers::authenticate(token_checker.clone()))
-        .and(db_filter.clone())
-        .and(warp::body::json())
-        .and_then(handlers::add_question);
-
-    let update_question_route = warp::put()
-        .and(warp::path("questions"))
-        .and(handlers::authenticate(token_checker.clone()))
-        .and(warp::path::param::<String>())
-        .and(warp::path::end())
-        .and(db_filter.clone())
-        .and(warp::body::json())
-        .and_then(handlers::update_question);
-
-    let delete_question_route = warp::delete()
-        .and(warp::path("questions"))
-        .and(handlers::authenticate(token_checker))
-        .and(warp::path::param::<String>())
-        .and(warp::path::end())
-        .and(db_filter.clone())
-        .and_then(handlers::delete_question);
-
-    let get_question_route = warp::get()
-        .and(warp::path("questions"))
-        .and(warp::path::param::<String>())
-        .and(warp::path::end())
-        .and(db_filter.clone())
-        .and_then(handlers::get_question);
-
-    let routes = add_usr_route
-        .or(login_user_route)
-        .or(list_questions_route)
-        .or(add_question_route)
-        .or(update_question_route)
-        .or(delete_question_route)
-        .or(get_question_route)
-        .with(cors)
-        .recover(handle_err)
-        .with(warp::trace::request());
+        .and_then(handlers::metrics)
+        .recover(handle_err);
+    let (stop_metrics_tx, stop_metrics_rx) = oneshot::channel::<()>();
+    let (metrics_addr, metrics_server) =
+        warp::serve(metrics_route).bind_with_graceful_shutdown(config.server.metrics_addr, async move {
+            stop_metrics_rx.await.ok();
+        });
+    event!(Level::INFO, "Serving metrics on {}", metrics_addr);
+    let mut metrics_server = tokio::spawn(metrics_server);
+
+    shutdown_signal().await;
+    readiness.begin_shutdown();
+    // keeps serving with `/readyz` failing for the load balancer to take the instance out, a second signal cuts it short
+    tokio::select! {
+        _ = tokio::time::sleep(config.server.shutdown_delay) => {},
+        _ = shutdown_signal() => {},
+    }
+
+    let deadline = tokio::time::Instant::now() + config.server.shutdown_timeout;
+    stop_tx.send(()).ok();
+    if tokio::time::timeout_at(deadline, &mut server).await.is_err() {
+        event!(
+            Level::WARN,
+            "Requests still in flight at the shutdown deadline, dropping them"
+        );
+        server.abort();
+    }
+    stop_metrics_tx.send(()).ok();
+    if tokio::time::timeout_at(deadline, &mut metrics_server).await.is_err() {
+        event!(
+            Level::WARN,
+            "Metrics scrape still in flight at the shutdown deadline, dropping it"
+        );
+        metrics_server.abort();
+    }
+    tasks.close();
+    if tokio::time::timeout_at(deadline, tasks.wait()).await.is_err() {
+        event!(
+            Level::WARN,
+            "{} background tasks still running at the shutdown deadline, dropping them",
+            tasks.len()
+        );
+    }
+    // the pool stops handing out connections right away, the wait is for the ones still in use
+    if tokio::time::timeout_at(deadline, db.close()).await.is_err() {
+        event!(Level::WARN, "Database connections still in use at the shutdown deadline");
+    }
+    event!(Level::INFO, "Shut down");
+}
 
-    warp::serve(routes).run(([0, 0, 0, 0], 7878)).await;
+/// Resolves on SIGTERM or SIGINT (Ctrl-C).
+async fn shutdown_signal() {
+    let mut sigterm = signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+    tokio::select! {
+        _ = sigterm.recv() => event!(Level::INFO, "Received SIGTERM, shutting down"),
+        _ = tokio::signal::ctrl_c() => event!(Level::INFO, "Received SIGINT, shutting down"),
+    }
 }
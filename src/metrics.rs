@@ -0,0 +1,127 @@
+use prometheus::{
+    Encoder, Gauge, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Process wide metrics, instrumented code records into them directly and `GET /metrics` renders them.
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    content_filter_call_duration: HistogramVec,
+    content_filter_errors: IntCounterVec,
+    logins: IntCounterVec,
+    pub db_pool_connections: IntGaugeVec,
+    pub db_pool_max_connections: IntGauge,
+    pub db_pool_acquire_seconds: Gauge,
+    pub questions: IntGaugeVec,
+}
+
+fn register<T: prometheus::core::Collector + Clone + 'static>(registry: &Registry, metric: T) -> T {
+    registry.register(Box::new(metric.clone())).expect("Duplicate metric");
+    metric
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        Metrics {
+            http_requests: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new("http_requests_total", "HTTP requests by route and status"),
+                    &["method", "route", "status"],
+                )
+                .unwrap(),
+            ),
+            http_request_duration: register(
+                &registry,
+                HistogramVec::new(
+                    HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route and status"),
+                    &["method", "route", "status"],
+                )
+                .unwrap(),
+            ),
+            content_filter_call_duration: register(
+                &registry,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "content_filter_call_duration_seconds",
+                        "Latency of the remote content filter calls, retries counting separately",
+                    ),
+                    &["backend", "result"],
+                )
+                .unwrap(),
+            ),
+            content_filter_errors: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new("content_filter_errors_total", "Failed remote content filter calls"),
+                    &["backend", "kind"],
+                )
+                .unwrap(),
+            ),
+            logins: register(
+                &registry,
+                IntCounterVec::new(Opts::new("logins_total", "Login attempts by result"), &["result"]).unwrap(),
+            ),
+            db_pool_connections: register(
+                &registry,
+                IntGaugeVec::new(Opts::new("db_pool_connections", "Database connections by state"), &["state"]).unwrap(),
+            ),
+            db_pool_max_connections: register(
+                &registry,
+                IntGauge::new("db_pool_max_connections", "Database connection pool size limit").unwrap(),
+            ),
+            db_pool_acquire_seconds: register(
+                &registry,
+                Gauge::new(
+                    "db_pool_acquire_seconds",
+                    "Time the last scrape waited for a database connection",
+                )
+                .unwrap(),
+            ),
+            questions: register(
+                &registry,
+                IntGaugeVec::new(Opts::new("questions", "Questions by status"), &["status"]).unwrap(),
+            ),
+            registry,
+        }
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let status = status.to_string();
+        let labels = [method, route, status.as_str()];
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_request_duration
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// `result` is `ok`, `error` or `timeout`, the latter two also counting as errors.
+    pub fn observe_content_filter_call(&self, backend: &str, result: &str, elapsed: Duration) {
+        self.content_filter_call_duration
+            .with_label_values(&[backend, result])
+            .observe(elapsed.as_secs_f64());
+        if result != "ok" {
+            self.content_filter_errors.with_label_values(&[backend, result]).inc();
+        }
+    }
+
+    /// `result` is `success`, `failure` or `locked`.
+    pub fn observe_login(&self, result: &str) {
+        self.logins.with_label_values(&[result]).inc();
+    }
+
+    /// Renders all the metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut buf = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buf) {
+            tracing::event!(tracing::Level::ERROR, "Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buf).unwrap_or_default()
+    }
+}
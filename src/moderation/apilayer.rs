@@ -0,0 +1,77 @@
+use super::base::{BadWordsServiceOkResponse, ContentFilter, FilterStatus};
+use error_handling::ServiceError;
+use serde::Deserialize;
+use tracing::{event, Level};
+
+const BAD_WORDS_SERVICE_URL: &str = "https://api.apilayer.com/bad_words?censor_character=*";
+
+#[derive(Deserialize)]
+struct BadWordsServiceErrorResponse {
+    message: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct ApiLayerFilter {
+    client: reqwest::Client,
+    api_key: String,
+}
+
+impl ApiLayerFilter {
+    pub fn new(api_key: String) -> Self {
+        ApiLayerFilter {
+            client: reqwest::Client::new(),
+            api_key,
+        }
+    }
+}
+
+impl ContentFilter for ApiLayerFilter {
+    async fn check(&self, text: String) -> Result<BadWordsServiceOkResponse, ServiceError> {
+        let res = self
+            .client
+            .post(BAD_WORDS_SERVICE_URL)
+            .header("APIKEY", &self.api_key)
+            .body(text)
+            .send()
+            .await
+            .map_err(|e| {
+                event!(Level::ERROR, "Error fetching data from Bad Words serviceL {}", e);
+                ServiceError::ExternalApiUnavailable
+            })?;
+
+        if !res.status().is_success() {
+            let status = res.status().as_u16();
+            let err = match res.status().is_server_error() {
+                true => ServiceError::ExternalApiUnavailable,
+                false => ServiceError::ExternalApiError,
+            };
+            let msg = match res.json::<BadWordsServiceErrorResponse>().await {
+                Ok(resp) => resp.message,
+                Err(_) => return Err(err),
+            };
+            event!(
+                Level::ERROR,
+                "Error occurred when calling external API (BadWords Service). Response status {}. Message: {}",
+                status,
+                msg
+            );
+            return Err(err);
+        }
+
+        match res.json::<BadWordsServiceOkResponse>().await {
+            Ok(resp) => Ok(resp),
+            Err(e) => {
+                event!(
+                    Level::ERROR,
+                    "Error occurred when calling external API (BadWords Service): {}",
+                    e
+                );
+                Err(ServiceError::ExternalApiError)
+            }
+        }
+    }
+
+    fn status(&self) -> FilterStatus {
+        FilterStatus::plain("apilayer")
+    }
+}
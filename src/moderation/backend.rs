@@ -0,0 +1,78 @@
+use super::apilayer::ApiLayerFilter;
+use super::base::{BadWordsServiceOkResponse, ContentFilter, FilterStatus};
+use super::local::LocalFilter;
+use super::resilient::{OpenCircuitPolicy, OpenCircuitPolicyKind, ResilienceSettings, ResilientFilter};
+use error_handling::ServiceError;
+use std::path::PathBuf;
+
+/// Content filter chosen with the `content_filter.backend` setting:
+/// `apilayer` (default) calls the remote bad-words service, `local` uses the built-in word lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentFilterKind {
+    #[default]
+    ApiLayer,
+    Local,
+}
+
+impl std::str::FromStr for ContentFilterKind {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "apilayer" => Ok(Self::ApiLayer),
+            "local" => Ok(Self::Local),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "expected apilayer or local",
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentFilterSettings {
+    pub backend: ContentFilterKind,
+    pub api_key: String,
+    /// Replaces the built-in word lists of the local filter.
+    pub words_file: Option<PathBuf>,
+    /// Word lists the local filter uses, all of them when empty.
+    pub languages: Vec<String>,
+    pub resilience: ResilienceSettings,
+    pub open_circuit_policy: OpenCircuitPolicyKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum ContentFilterBackend {
+    ApiLayer(ResilientFilter<ApiLayerFilter>),
+    Local(LocalFilter),
+}
+
+impl ContentFilterBackend {
+    pub fn new(settings: ContentFilterSettings) -> Result<Self, std::io::Error> {
+        let local = || LocalFilter::from_settings(settings.words_file.as_deref(), &settings.languages);
+        match settings.backend {
+            ContentFilterKind::ApiLayer => Ok(Self::ApiLayer(ResilientFilter::new(
+                ApiLayerFilter::new(settings.api_key.clone()),
+                settings.resilience.clone(),
+                OpenCircuitPolicy::new(settings.open_circuit_policy, local)?,
+            ))),
+            ContentFilterKind::Local => Ok(Self::Local(local()?)),
+        }
+    }
+}
+
+impl ContentFilter for ContentFilterBackend {
+    async fn check(&self, text: String) -> Result<BadWordsServiceOkResponse, ServiceError> {
+        match self {
+            Self::ApiLayer(filter) => filter.check(text).await,
+            Self::Local(filter) => filter.check(text).await,
+        }
+    }
+
+    fn status(&self) -> FilterStatus {
+        match self {
+            Self::ApiLayer(filter) => filter.status(),
+            Self::Local(filter) => filter.status(),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+#![allow(dead_code)]
+
+use error_handling::ServiceError;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BadWordsServiceOkResponse {
+    pub content: String,
+    pub bad_words_total: i64,
+    pub bad_words_list: Vec<BadWord>,
+    pub censored_content: String,
+    /// Set when the text was accepted unchecked and has to be put on the moderation queue.
+    #[serde(default)]
+    pub needs_review: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BadWord {
+    pub original: String,
+    pub word: String,
+    pub deviations: i64,
+    pub info: i64,
+    pub start: i64,
+    pub end: i64,
+    #[serde(rename = "replacedLen")]
+    pub replaced_len: i64,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FilterStatus {
+    pub backend: &'static str,
+    pub circuit: Option<CircuitState>,
+    pub consecutive_failures: u32,
+    pub open_circuit_policy: Option<&'static str>,
+}
+
+impl FilterStatus {
+    pub fn plain(backend: &'static str) -> Self {
+        FilterStatus {
+            backend,
+            circuit: None,
+            consecutive_failures: 0,
+            open_circuit_policy: None,
+        }
+    }
+}
+
+pub trait ContentFilter: std::fmt::Debug + Clone + Send + Sync + 'static {
+    fn check(&self, text: String) -> impl Future<Output = Result<BadWordsServiceOkResponse, ServiceError>> + Send;
+
+    fn status(&self) -> FilterStatus;
+}
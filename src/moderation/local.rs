@@ -0,0 +1,272 @@
+use super::base::{BadWord, BadWordsServiceOkResponse, ContentFilter, FilterStatus};
+use error_handling::ServiceError;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+const DEFAULT_WORD_LISTS: &str = include_str!("bad_words.txt");
+const CENSOR_CHAR: char = '*';
+
+fn unleet(c: char) -> Option<char> {
+    match c {
+        '0' => Some('o'),
+        '1' | '!' => Some('i'),
+        '3' => Some('e'),
+        '4' | '@' => Some('a'),
+        '5' | '$' => Some('s'),
+        '7' => Some('t'),
+        '8' => Some('b'),
+        '9' => Some('g'),
+        _ => None,
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || unleet(c).is_some()
+}
+
+/// Lowercases the token and maps leetspeak characters back to letters, returning
+/// the normalized form together with the number of substituted characters.
+/// Tokens without any letter, like plain numbers, are only lowercased.
+fn normalize(token: &[char]) -> (String, i64) {
+    if !token.iter().any(|c| c.is_alphabetic()) {
+        return (token.iter().flat_map(|c| c.to_lowercase()).collect(), 0);
+    }
+    let mut deviations = 0;
+    let normalized = token
+        .iter()
+        .flat_map(|c| match unleet(*c) {
+            Some(letter) => {
+                deviations += 1;
+                vec![letter]
+            }
+            None => c.to_lowercase().collect(),
+        })
+        .collect();
+    (normalized, deviations)
+}
+
+fn squeeze(word: &str) -> String {
+    let mut squeezed: Vec<char> = word.chars().collect();
+    squeezed.dedup();
+    squeezed.into_iter().collect()
+}
+
+/// Parses word lists: one word per line, `[lang]` headers open a per-language list,
+/// `#` starts a comment. Words listed before any header belong to every language.
+fn parse_word_lists(src: &str) -> HashMap<String, Vec<String>> {
+    let mut lists: HashMap<String, Vec<String>> = HashMap::new();
+    let mut lang = String::new();
+    for line in src.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            lang = line[1..line.len() - 1].trim().to_lowercase();
+            continue;
+        }
+        lists.entry(lang.clone()).or_default().push(line.to_lowercase());
+    }
+    lists
+}
+
+#[derive(Debug, Default)]
+struct Dictionary {
+    words: HashMap<String, String>,
+    squeezed: HashMap<String, String>,
+}
+
+impl Dictionary {
+    fn lookup(&self, token: &[char]) -> Option<(String, i64)> {
+        let (normalized, deviations) = normalize(token);
+        if let Some(word) = self.words.get(&normalized) {
+            return Some((word.clone(), deviations));
+        }
+        let squeezed = squeeze(&normalized);
+        let repeats = (normalized.chars().count() - squeezed.chars().count()) as i64;
+        match self.squeezed.get(&squeezed) {
+            Some(word) if normalized.chars().count() > word.chars().count() => Some((word.clone(), deviations + repeats)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LocalFilter {
+    dictionary: Arc<Dictionary>,
+}
+
+impl LocalFilter {
+    /// Builds the filter from word lists in `src`, keeping only the lists for `langs`.
+    /// An empty `langs` slice activates every list.
+    pub fn new(src: &str, langs: &[String]) -> Self {
+        let mut dictionary = Dictionary::default();
+        for (lang, words) in parse_word_lists(src) {
+            if !lang.is_empty() && !langs.is_empty() && !langs.contains(&lang) {
+                continue;
+            }
+            for word in words {
+                dictionary.squeezed.insert(squeeze(&word), word.clone());
+                dictionary.words.insert(word.clone(), word);
+            }
+        }
+        LocalFilter {
+            dictionary: Arc::new(dictionary),
+        }
+    }
+
+    /// Loads the word lists from `words_file`, or the built-in ones, keeping only `languages` unless empty.
+    pub fn from_settings(words_file: Option<&Path>, languages: &[String]) -> Result<Self, std::io::Error> {
+        let src = match words_file {
+            Some(path) => std::fs::read_to_string(path)?,
+            None => DEFAULT_WORD_LISTS.to_string(),
+        };
+        let langs: Vec<String> = languages.iter().map(|lang| lang.trim().to_lowercase()).collect();
+        Ok(Self::new(&src, &langs))
+    }
+
+    /// Looks the token up as is and, failing that, without the leading and trailing
+    /// punctuation that doubles as leetspeak (e.g. the `!` in `shit!`).
+    fn match_token(&self, chars: &[char], start: usize, end: usize) -> Option<(usize, usize, String, i64)> {
+        if let Some((word, deviations)) = self.dictionary.lookup(&chars[start..end]) {
+            return Some((start, end, word, deviations));
+        }
+        let (mut trimmed_start, mut trimmed_end) = (start, end);
+        while trimmed_start < trimmed_end && !chars[trimmed_start].is_alphanumeric() {
+            trimmed_start += 1;
+        }
+        while trimmed_end > trimmed_start && !chars[trimmed_end - 1].is_alphanumeric() {
+            trimmed_end -= 1;
+        }
+        if (trimmed_start, trimmed_end) == (start, end) || trimmed_start == trimmed_end {
+            return None;
+        }
+        self.dictionary
+            .lookup(&chars[trimmed_start..trimmed_end])
+            .map(|(word, deviations)| (trimmed_start, trimmed_end, word, deviations))
+    }
+
+    pub fn scan(&self, text: &str) -> BadWordsServiceOkResponse {
+        let chars: Vec<char> = text.chars().collect();
+        let mut censored = chars.clone();
+        let mut bad_words_list = Vec::new();
+
+        let mut start = 0;
+        while start < chars.len() {
+            if !is_word_char(chars[start]) {
+                start += 1;
+                continue;
+            }
+            let mut end = start;
+            while end < chars.len() && is_word_char(chars[end]) {
+                end += 1;
+            }
+            let token_end = end;
+            if let Some((start, end, word, deviations)) = self.match_token(&chars, start, end) {
+                censored[start..end].iter_mut().for_each(|c| *c = CENSOR_CHAR);
+                bad_words_list.push(BadWord {
+                    original: chars[start..end].iter().collect(),
+                    word,
+                    deviations,
+                    info: 0,
+                    start: start as i64,
+                    end: end as i64,
+                    replaced_len: (end - start) as i64,
+                });
+            }
+            start = token_end;
+        }
+
+        BadWordsServiceOkResponse {
+            content: text.to_string(),
+            bad_words_total: bad_words_list.len() as i64,
+            bad_words_list,
+            censored_content: censored.into_iter().collect(),
+            needs_review: false,
+        }
+    }
+}
+
+impl ContentFilter for LocalFilter {
+    async fn check(&self, text: String) -> Result<BadWordsServiceOkResponse, ServiceError> {
+        Ok(self.scan(&text))
+    }
+
+    fn status(&self) -> FilterStatus {
+        FilterStatus::plain("local")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: &str = "
+        # shared by every language
+        damn
+        [en]
+        ass
+        shit  # trailing comment
+        [DE]
+        Mist
+    ";
+
+    fn chars(token: &str) -> Vec<char> {
+        token.chars().collect()
+    }
+
+    fn filter() -> LocalFilter {
+        LocalFilter::new(WORDS, &[])
+    }
+
+    #[test]
+    fn normalize_maps_leetspeak_back_to_letters() {
+        assert_eq!(normalize(&chars("Sh1T")), ("shit".to_string(), 1));
+        assert_eq!(normalize(&chars("@sS")), ("ass".to_string(), 1));
+    }
+
+    #[test]
+    fn normalize_leaves_numbers_alone() {
+        assert_eq!(normalize(&chars("455")), ("455".to_string(), 0));
+        assert_eq!(normalize(&chars("1337")), ("1337".to_string(), 0));
+    }
+
+    #[test]
+    fn parse_word_lists_groups_words_by_language() {
+        let lists = parse_word_lists(WORDS);
+        assert_eq!(lists[""], vec!["damn"]);
+        assert_eq!(lists["en"], vec!["ass", "shit"]);
+        assert_eq!(lists["de"], vec!["mist"]);
+    }
+
+    #[test]
+    fn new_keeps_only_the_requested_languages() {
+        let filter = LocalFilter::new(WORDS, &["de".to_string()]);
+        assert_eq!(filter.scan("damn Mist").bad_words_total, 2);
+        assert_eq!(filter.scan("shit").bad_words_total, 0);
+    }
+
+    #[test]
+    fn scan_censors_matches_and_reports_their_position() {
+        let res = filter().scan("Oh sh1t, what a mess!");
+        assert_eq!(res.censored_content, "Oh ****, what a mess!");
+        assert_eq!(res.bad_words_total, 1);
+        let found = &res.bad_words_list[0];
+        assert_eq!((found.original.as_str(), found.word.as_str()), ("sh1t", "shit"));
+        assert_eq!((found.start, found.end, found.deviations), (3, 7, 1));
+    }
+
+    #[test]
+    fn scan_matches_repeated_letters_and_trailing_punctuation() {
+        assert_eq!(filter().scan("shiiiit").censored_content, "*******");
+        assert_eq!(filter().scan("damn!").censored_content, "****!");
+    }
+
+    #[test]
+    fn scan_ignores_numbers_and_clean_words() {
+        let res = filter().scan("Order 455 shipped in 1337 boxes, assume it's classic");
+        assert_eq!(res.bad_words_total, 0);
+        assert_eq!(res.censored_content, res.content);
+    }
+}
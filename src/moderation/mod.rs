@@ -0,0 +1,9 @@
+mod apilayer;
+mod backend;
+mod base;
+mod local;
+mod resilient;
+
+pub use backend::*;
+pub use base::*;
+pub use resilient::{OpenCircuitPolicyKind, ResilienceSettings};
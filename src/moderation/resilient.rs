@@ -0,0 +1,456 @@
+use super::base::{BadWordsServiceOkResponse, CircuitState, ContentFilter, FilterStatus};
+use super::local::LocalFilter;
+use crate::metrics::METRICS;
+use error_handling::ServiceError;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{event, Level};
+
+#[derive(Debug, Clone)]
+pub struct ResilienceSettings {
+    pub timeout: Duration,
+    pub retries: u32,
+    pub backoff: Duration,
+    pub failure_threshold: u32,
+    pub cooldown: Duration,
+}
+
+/// Which `OpenCircuitPolicy` to use, set with the `content_filter.open_circuit_policy` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OpenCircuitPolicyKind {
+    #[default]
+    Reject,
+    Queue,
+    Local,
+}
+
+impl std::str::FromStr for OpenCircuitPolicyKind {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(Self::Reject),
+            "queue" => Ok(Self::Queue),
+            "local" => Ok(Self::Local),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "expected reject, queue or local",
+            )),
+        }
+    }
+}
+
+/// What to do with a text when the remote filter is unavailable,
+/// i.e. the circuit is open or all the retries have failed.
+#[derive(Debug, Clone)]
+pub enum OpenCircuitPolicy {
+    /// Fail the request with `ExternalApiError`.
+    Reject,
+    /// Accept the text as is, flagged for the caller to put its owner on the moderation queue.
+    Queue,
+    /// Censor the text with the built-in word lists.
+    Fallback(LocalFilter),
+}
+
+impl OpenCircuitPolicy {
+    pub fn new(
+        kind: OpenCircuitPolicyKind,
+        fallback: impl FnOnce() -> Result<LocalFilter, std::io::Error>,
+    ) -> Result<Self, std::io::Error> {
+        match kind {
+            OpenCircuitPolicyKind::Reject => Ok(Self::Reject),
+            OpenCircuitPolicyKind::Queue => Ok(Self::Queue),
+            OpenCircuitPolicyKind::Local => Ok(Self::Fallback(fallback()?)),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Reject => "reject",
+            Self::Queue => "queue",
+            Self::Fallback(_) => "local",
+        }
+    }
+
+    async fn apply(&self, text: String) -> Result<BadWordsServiceOkResponse, ServiceError> {
+        match self {
+            Self::Reject => Err(ServiceError::ExternalApiError),
+            Self::Fallback(filter) => filter.check(text).await,
+            Self::Queue => Ok(BadWordsServiceOkResponse {
+                content: text.clone(),
+                bad_words_total: 0,
+                bad_words_list: Vec::new(),
+                censored_content: text,
+                needs_review: true,
+            }),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Breaker {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+}
+
+/// Lets a single call through the breaker. The outcome has to be reported with
+/// `success` or `failure`, a half-open trial dropped before that, e.g. because the
+/// request got cancelled, counts as a failure so the circuit can't get stuck half-open.
+struct Permit<'a, F: ContentFilter> {
+    filter: &'a ResilientFilter<F>,
+    trial: bool,
+}
+
+impl<F: ContentFilter> Permit<'_, F> {
+    fn success(mut self) {
+        self.trial = false;
+        self.filter.on_success();
+    }
+
+    fn failure(mut self) {
+        self.trial = false;
+        self.filter.on_failure();
+    }
+}
+
+impl<F: ContentFilter> Drop for Permit<'_, F> {
+    fn drop(&mut self) {
+        if self.trial {
+            event!(Level::WARN, "Content filter trial request dropped before completing");
+            self.filter.on_failure();
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ResilientFilter<F: ContentFilter> {
+    inner: F,
+    settings: ResilienceSettings,
+    policy: OpenCircuitPolicy,
+    breaker: Arc<Mutex<Breaker>>,
+}
+
+impl<F: ContentFilter> ResilientFilter<F> {
+    pub fn new(inner: F, settings: ResilienceSettings, policy: OpenCircuitPolicy) -> Self {
+        ResilientFilter {
+            inner,
+            settings,
+            policy,
+            breaker: Arc::new(Mutex::new(Breaker {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                trial_in_flight: false,
+            })),
+        }
+    }
+
+    /// Decides whether a call may go through, moving an open circuit
+    /// to half-open once the cooldown has elapsed and letting a single trial call in.
+    fn acquire(&self) -> Option<Permit<'_, F>> {
+        let mut breaker = self.breaker.lock().unwrap();
+        let trial = match breaker.state {
+            CircuitState::Closed => false,
+            CircuitState::Open => {
+                let cooled_down = breaker
+                    .opened_at
+                    .map(|at| at.elapsed() >= self.settings.cooldown)
+                    .unwrap_or(true);
+                if !cooled_down {
+                    return None;
+                }
+                event!(Level::INFO, "Content filter circuit half-open, sending trial request");
+                breaker.state = CircuitState::HalfOpen;
+                true
+            }
+            CircuitState::HalfOpen => {
+                if breaker.trial_in_flight {
+                    return None;
+                }
+                true
+            }
+        };
+        breaker.trial_in_flight = trial;
+        Some(Permit { filter: self, trial })
+    }
+
+    fn on_success(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        if breaker.state != CircuitState::Closed {
+            event!(Level::INFO, "Content filter circuit closed");
+        }
+        breaker.state = CircuitState::Closed;
+        breaker.consecutive_failures = 0;
+        breaker.opened_at = None;
+        breaker.trial_in_flight = false;
+    }
+
+    fn on_failure(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.consecutive_failures += 1;
+        breaker.trial_in_flight = false;
+        let should_open =
+            breaker.state == CircuitState::HalfOpen || breaker.consecutive_failures >= self.settings.failure_threshold;
+        if should_open && breaker.state != CircuitState::Open {
+            event!(
+                Level::WARN,
+                "Content filter circuit opened after {} consecutive failures, retrying in {:?}",
+                breaker.consecutive_failures,
+                self.settings.cooldown
+            );
+        }
+        if should_open {
+            breaker.state = CircuitState::Open;
+            breaker.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Calls the remote filter, retrying timeouts and `ExternalApiUnavailable` errors with
+    /// exponential backoff. Any other error means the service is up but refused the text, so it's returned right away.
+    async fn call_with_retries(&self, text: &str) -> Result<BadWordsServiceOkResponse, ServiceError> {
+        let backend = self.inner.status().backend;
+        let mut attempt = 0;
+        loop {
+            let started = Instant::now();
+            let res = tokio::time::timeout(self.settings.timeout, self.inner.check(text.to_string())).await;
+            let result = match &res {
+                Ok(Ok(_)) => "ok",
+                Ok(Err(_)) => "error",
+                Err(_) => "timeout",
+            };
+            METRICS.observe_content_filter_call(backend, result, started.elapsed());
+            let err = match res {
+                Ok(Ok(resp)) => return Ok(resp),
+                Ok(Err(ServiceError::ExternalApiUnavailable)) => ServiceError::ExternalApiUnavailable,
+                Ok(Err(e)) => return Err(e),
+                Err(_) => {
+                    event!(Level::WARN, "Content filter timed out after {:?}", self.settings.timeout);
+                    ServiceError::ExternalApiUnavailable
+                }
+            };
+            if attempt >= self.settings.retries {
+                return Err(err);
+            }
+            event!(Level::WARN, "Content filter call failed, attempt {}", attempt + 1);
+            tokio::time::sleep(self.settings.backoff.saturating_mul(2u32.saturating_pow(attempt))).await;
+            attempt += 1;
+        }
+    }
+}
+
+impl<F: ContentFilter> ContentFilter for ResilientFilter<F> {
+    async fn check(&self, text: String) -> Result<BadWordsServiceOkResponse, ServiceError> {
+        let Some(permit) = self.acquire() else {
+            event!(
+                Level::WARN,
+                "Content filter circuit open, applying '{}' policy",
+                self.policy.name()
+            );
+            return self.policy.apply(text).await;
+        };
+        match self.call_with_retries(&text).await {
+            Ok(resp) => {
+                permit.success();
+                Ok(resp)
+            }
+            Err(ServiceError::ExternalApiUnavailable) => {
+                permit.failure();
+                self.policy.apply(text).await
+            }
+            // the service answered, so it counts as available
+            Err(e) => {
+                permit.success();
+                Err(e)
+            }
+        }
+    }
+
+    fn status(&self) -> FilterStatus {
+        let breaker = self.breaker.lock().unwrap();
+        FilterStatus {
+            circuit: Some(breaker.state),
+            consecutive_failures: breaker.consecutive_failures,
+            open_circuit_policy: Some(self.policy.name()),
+            ..self.inner.status()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[derive(Clone, Copy, Debug)]
+    enum Outcome {
+        Ok,
+        Unavailable,
+        Refused,
+        Hang,
+    }
+
+    /// Answers with the scripted outcomes in order, then with `Ok`.
+    #[derive(Clone, Debug, Default)]
+    struct ScriptedFilter {
+        outcomes: Arc<Mutex<VecDeque<Outcome>>>,
+        calls: Arc<AtomicU32>,
+    }
+
+    impl ScriptedFilter {
+        fn new(outcomes: &[Outcome]) -> Self {
+            ScriptedFilter {
+                outcomes: Arc::new(Mutex::new(outcomes.iter().copied().collect())),
+                ..Default::default()
+            }
+        }
+
+        fn calls(&self) -> u32 {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    impl ContentFilter for ScriptedFilter {
+        async fn check(&self, text: String) -> Result<BadWordsServiceOkResponse, ServiceError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let outcome = self.outcomes.lock().unwrap().pop_front().unwrap_or(Outcome::Ok);
+            match outcome {
+                Outcome::Ok => Ok(BadWordsServiceOkResponse {
+                    content: text.clone(),
+                    bad_words_total: 0,
+                    bad_words_list: Vec::new(),
+                    censored_content: text,
+                    needs_review: false,
+                }),
+                Outcome::Unavailable => Err(ServiceError::ExternalApiUnavailable),
+                Outcome::Refused => Err(ServiceError::ExternalApiError),
+                Outcome::Hang => std::future::pending().await,
+            }
+        }
+
+        fn status(&self) -> FilterStatus {
+            FilterStatus::plain("scripted")
+        }
+    }
+
+    const COOLDOWN: Duration = Duration::from_millis(50);
+
+    fn resilient(inner: &ScriptedFilter, retries: u32, policy: OpenCircuitPolicy) -> ResilientFilter<ScriptedFilter> {
+        let settings = ResilienceSettings {
+            timeout: Duration::from_millis(100),
+            retries,
+            backoff: Duration::from_millis(1),
+            failure_threshold: 2,
+            cooldown: COOLDOWN,
+        };
+        ResilientFilter::new(inner.clone(), settings, policy)
+    }
+
+    fn circuit(filter: &ResilientFilter<ScriptedFilter>) -> CircuitState {
+        filter.status().circuit.unwrap()
+    }
+
+    #[tokio::test]
+    async fn retries_unavailable_calls_up_to_the_limit() {
+        let inner = ScriptedFilter::new(&[Outcome::Unavailable; 4]);
+        let filter = resilient(&inner, 2, OpenCircuitPolicy::Reject);
+
+        let res = filter.check("text".to_string()).await;
+
+        assert!(matches!(res, Err(ServiceError::ExternalApiError)));
+        assert_eq!(inner.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_timeouts() {
+        let inner = ScriptedFilter::new(&[Outcome::Hang, Outcome::Ok]);
+        let filter = resilient(&inner, 1, OpenCircuitPolicy::Reject);
+
+        assert!(filter.check("text".to_string()).await.is_ok());
+        assert_eq!(inner.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_refused_calls() {
+        let inner = ScriptedFilter::new(&[Outcome::Refused, Outcome::Refused]);
+        let filter = resilient(&inner, 2, OpenCircuitPolicy::Reject);
+
+        for _ in 0..2 {
+            assert!(matches!(
+                filter.check("text".to_string()).await,
+                Err(ServiceError::ExternalApiError)
+            ));
+        }
+        assert_eq!(inner.calls(), 2);
+        assert_eq!(circuit(&filter), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn circuit_opens_half_opens_and_closes() {
+        let inner = ScriptedFilter::new(&[Outcome::Unavailable, Outcome::Unavailable]);
+        let filter = resilient(&inner, 0, OpenCircuitPolicy::Reject);
+
+        assert!(filter.check("text".to_string()).await.is_err());
+        assert_eq!(circuit(&filter), CircuitState::Closed);
+        assert!(filter.check("text".to_string()).await.is_err());
+        assert_eq!(circuit(&filter), CircuitState::Open);
+
+        // rejected without calling the backend until the cooldown is over
+        assert!(filter.check("text".to_string()).await.is_err());
+        assert_eq!(inner.calls(), 2);
+
+        tokio::time::sleep(COOLDOWN).await;
+        let trial = filter.acquire().unwrap();
+        assert_eq!(circuit(&filter), CircuitState::HalfOpen);
+        assert!(filter.acquire().is_none(), "a single trial at a time");
+        trial.success();
+        assert_eq!(circuit(&filter), CircuitState::Closed);
+        assert!(filter.check("text".to_string()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn failed_trial_reopens_the_circuit() {
+        let inner = ScriptedFilter::new(&[Outcome::Unavailable; 3]);
+        let filter = resilient(&inner, 0, OpenCircuitPolicy::Reject);
+        for _ in 0..2 {
+            assert!(filter.check("text".to_string()).await.is_err());
+        }
+
+        tokio::time::sleep(COOLDOWN).await;
+        assert!(filter.check("text".to_string()).await.is_err());
+
+        assert_eq!(inner.calls(), 3);
+        assert_eq!(circuit(&filter), CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn dropped_trial_counts_as_a_failure() {
+        let inner = ScriptedFilter::new(&[Outcome::Unavailable, Outcome::Unavailable, Outcome::Hang]);
+        let filter = resilient(&inner, 0, OpenCircuitPolicy::Reject);
+        for _ in 0..2 {
+            assert!(filter.check("text".to_string()).await.is_err());
+        }
+
+        tokio::time::sleep(COOLDOWN).await;
+        let cancelled = tokio::time::timeout(Duration::from_millis(10), filter.check("text".to_string())).await;
+
+        assert!(cancelled.is_err());
+        assert_eq!(circuit(&filter), CircuitState::Open);
+        tokio::time::sleep(COOLDOWN).await;
+        assert!(filter.check("text".to_string()).await.is_ok());
+        assert_eq!(circuit(&filter), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn queue_policy_flags_the_text_for_review() {
+        let inner = ScriptedFilter::new(&[Outcome::Unavailable]);
+        let filter = resilient(&inner, 0, OpenCircuitPolicy::Queue);
+
+        let resp = filter.check("text".to_string()).await.unwrap();
+
+        assert!(resp.needs_review);
+        assert_eq!(resp.censored_content, "text");
+    }
+}
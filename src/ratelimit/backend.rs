@@ -0,0 +1,53 @@
+use super::base::{Quota, RateLimitStore};
+use super::memory::MemoryRateLimiter;
+use crate::storage::Db;
+use error_handling::ServiceError;
+
+/// Where the token buckets live, set with the `rate_limit.store` setting:
+/// `memory` (default) keeps them per process, `postgres` shares them between all instances of the service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RateLimitStoreKind {
+    #[default]
+    Memory,
+    Postgres,
+}
+
+impl std::str::FromStr for RateLimitStoreKind {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "memory" => Ok(Self::Memory),
+            "postgres" => Ok(Self::Postgres),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "expected memory or postgres",
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum RateLimitBackend {
+    Memory(MemoryRateLimiter),
+    Postgres(Db),
+}
+
+impl RateLimitBackend {
+    pub fn new(kind: RateLimitStoreKind, db: Db) -> Self {
+        match kind {
+            RateLimitStoreKind::Memory => Self::Memory(MemoryRateLimiter::default()),
+            RateLimitStoreKind::Postgres => Self::Postgres(db),
+        }
+    }
+}
+
+impl RateLimitStore for RateLimitBackend {
+    async fn acquire(&self, key: String, quota: Quota) -> Result<(), ServiceError> {
+        match self {
+            Self::Memory(store) => store.acquire(key, quota).await,
+            // boxed, the transaction makes for a future big enough to overflow the stack of debug builds
+            Self::Postgres(store) => Box::pin(store.acquire(key, quota)).await,
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use error_handling::ServiceError;
+use std::future::Future;
+use std::str::FromStr;
+
+/// Token bucket holding up to `capacity` tokens, refilled at `capacity` tokens per `period_secs`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quota {
+    pub capacity: u32,
+    pub period_secs: u32,
+}
+
+impl Quota {
+    fn refill_rate(&self) -> f64 {
+        self.capacity as f64 / self.period_secs as f64
+    }
+
+    /// Tokens in a bucket that had `tokens` left `elapsed_secs` ago.
+    pub fn refill(&self, tokens: f64, elapsed_secs: f64) -> f64 {
+        (tokens + elapsed_secs.max(0.0) * self.refill_rate()).min(self.capacity as f64)
+    }
+
+    /// Seconds until a bucket with `tokens` left is full again.
+    pub fn secs_until_full(&self, tokens: f64) -> f64 {
+        (self.capacity as f64 - tokens).max(0.0) / self.refill_rate()
+    }
+
+    /// Takes a token out of `tokens`, returning what is left or, when the bucket is empty,
+    /// the tokens as they are and the seconds until the next one.
+    pub fn take(&self, tokens: f64) -> (f64, Result<(), ServiceError>) {
+        if tokens >= 1.0 {
+            return (tokens - 1.0, Ok(()));
+        }
+        let retry_after = ((1.0 - tokens) / self.refill_rate()).ceil().max(1.0) as u64;
+        (tokens, Err(ServiceError::RateLimited(retry_after)))
+    }
+}
+
+impl FromStr for Quota {
+    type Err = std::io::Error;
+
+    /// Parses `<requests>/<seconds>`, e.g. `10/60` for ten requests a minute.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidInput, "expected <requests>/<seconds> or off");
+        let (capacity, period_secs) = s.split_once('/').ok_or_else(invalid)?;
+        let quota = Quota {
+            capacity: capacity.trim().parse().map_err(|_| invalid())?,
+            period_secs: period_secs.trim().parse().map_err(|_| invalid())?,
+        };
+        match quota.capacity > 0 && quota.period_secs > 0 {
+            true => Ok(quota),
+            false => Err(invalid()),
+        }
+    }
+}
+
+/// Keeps the token buckets, keyed by what is limited, e.g. `login:ip:10.0.0.1`.
+pub trait RateLimitStore: std::fmt::Debug + Clone + Send + Sync + 'static {
+    /// Takes a token from the `key` bucket, rejects with `RateLimited` when it's empty.
+    fn acquire(&self, key: String, quota: Quota) -> impl Future<Output = Result<(), ServiceError>> + Send;
+}
+
+/// Quotas of the rate limited routes, `None` turns a limit off.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimits {
+    pub login_per_ip: Option<Quota>,
+    /// Shared by the routes sending mails, each with a bucket of its own.
+    pub mail_per_ip: Option<Quota>,
+    pub questions_per_user: Option<Quota>,
+}
@@ -0,0 +1,47 @@
+use super::base::{Quota, RateLimitStore};
+use error_handling::ServiceError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Past this many buckets, the full ones get dropped, they hold nothing a new bucket wouldn't.
+const PRUNE_ABOVE: usize = 10_000;
+
+#[derive(Debug)]
+struct Bucket {
+    quota: Quota,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn tokens_at(&self, now: Instant) -> f64 {
+        self.quota.refill(self.tokens, (now - self.updated_at).as_secs_f64())
+    }
+}
+
+/// Buckets kept in process memory, each instance of the service limits on its own.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryRateLimiter {
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+}
+
+impl RateLimitStore for MemoryRateLimiter {
+    async fn acquire(&self, key: String, quota: Quota) -> Result<(), ServiceError> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > PRUNE_ABOVE {
+            buckets.retain(|_, bucket| bucket.tokens_at(now) < bucket.quota.capacity as f64);
+        }
+
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            quota,
+            tokens: quota.capacity as f64,
+            updated_at: now,
+        });
+        let (left, res) = quota.take(bucket.tokens_at(now));
+        bucket.tokens = left;
+        bucket.updated_at = now;
+        res
+    }
+}
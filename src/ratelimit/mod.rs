@@ -0,0 +1,8 @@
+mod backend;
+mod base;
+mod memory;
+
+pub use backend::*;
+pub use base::*;
+#[cfg(test)]
+pub use memory::MemoryRateLimiter;
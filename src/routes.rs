@@ -0,0 +1,799 @@
+use crate::auth::AuthProvider;
+use crate::handlers;
+use crate::mail::Mailer;
+use crate::moderation::ContentFilter;
+use crate::ratelimit::{RateLimitStore, RateLimits};
+use crate::storage::{AccountTokenStore, HealthStore, MessageStore, QuestionStore, ReviewStore, TokenStore, UserStore};
+use crate::types::account::VerificationPolicy;
+use crate::types::assignment::AssignmentStrategy;
+use crate::types::auth::LoginLockout;
+use crate::types::health::Readiness;
+use crate::types::role::Permission;
+use error_handling::{handle_err, with_request_id};
+use std::net::IpAddr;
+use std::time::Instant;
+use tokio_util::task::TaskTracker;
+use warp::{http, Filter, Rejection, Reply};
+
+/// What the routes are served with. The store and the backends are generic for the tests to swap in fakes.
+#[derive(Debug, Clone)]
+pub struct Services<S, T, F, M, L> {
+    pub db: S,
+    pub auth: T,
+    pub content_filter: F,
+    pub mailer: M,
+    pub rate_limiter: L,
+    /// Background work the shutdown waits for, like the password reset mails.
+    pub tasks: TaskTracker,
+    pub readiness: Readiness,
+}
+
+/// The part of `Config` the routes are built with.
+#[derive(Debug, Clone)]
+pub struct RouteSettings {
+    pub cors_origins: Vec<String>,
+    pub public_url: String,
+    pub moderator_key: String,
+    pub lockout: LoginLockout,
+    pub email_verification: VerificationPolicy,
+    pub auto_assign: AssignmentStrategy,
+    pub rate_limits: RateLimits,
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+/// All of the API routes, with their errors answered, their metrics recorded and the request id attached.
+pub fn api<S, T, F, M, L>(
+    services: Services<S, T, F, M, L>,
+    settings: RouteSettings,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone
+where
+    S: QuestionStore + UserStore + TokenStore + AccountTokenStore + MessageStore + ReviewStore + HealthStore,
+    T: AuthProvider + Sync + 'static,
+    F: ContentFilter,
+    M: Mailer,
+    L: RateLimitStore,
+{
+    let cors = warp::cors()
+        .allow_methods(vec![http::Method::PUT, http::Method::PATCH, http::Method::DELETE])
+        .allow_origins(settings.cors_origins.iter().map(String::as_str))
+        .allow_header("content-type");
+
+    let token_issuer = services.auth;
+    let token_checker = token_issuer.clone();
+    let token_refresher = token_issuer.clone();
+
+    let db = services.db;
+    let db_conn = db.clone();
+    let db_filter = warp::any().map(move || db_conn.clone());
+
+    let content_filter = services.content_filter;
+    let content_filter = warp::any().map(move || content_filter.clone());
+
+    let assignment_strategy = settings.auto_assign;
+    let assignment_strategy = warp::any().map(move || assignment_strategy);
+
+    let mailer = services.mailer;
+    let mailer = warp::any().map(move || mailer.clone());
+
+    let verification_policy = settings.email_verification;
+    let verification_policy = warp::any().map(move || verification_policy);
+
+    let public_url = settings.public_url;
+    let public_url = warp::any().map(move || public_url.clone());
+
+    let rate_limiter = services.rate_limiter;
+    let rate_limits = settings.rate_limits;
+    let client_ip = handlers::client_ip(settings.trusted_proxies);
+
+    let login_lockout = settings.lockout;
+    let login_lockout = warp::any().map(move || login_lockout);
+
+    let moderator_key = settings.moderator_key;
+
+    let background_tasks = services.tasks;
+    let readiness = services.readiness;
+
+    let add_usr_route = warp::path!("users")
+        .and(warp::post())
+        .and(handlers::rate_limit_ip(
+            client_ip.clone(),
+            rate_limiter.clone(),
+            "signup",
+            rate_limits.mail_per_ip,
+        ))
+        .and(handlers::json_body())
+        .and(handlers::parse_auth_headers())
+        .and(db_filter.clone())
+        .and(warp::any().map(move || moderator_key.clone()))
+        .and(mailer.clone())
+        .and(public_url.clone())
+        .and_then(handlers::add_user);
+
+    let verify_email_route = warp::path!("users" / "verify")
+        .and(warp::get())
+        .and(warp::query())
+        .and(db_filter.clone())
+        .and_then(handlers::verify_email);
+
+    let login_user_route = warp::path!("login")
+        .and(warp::post())
+        .and(handlers::rate_limit_ip(
+            client_ip.clone(),
+            rate_limiter.clone(),
+            "login",
+            rate_limits.login_per_ip,
+        ))
+        .and(handlers::json_body())
+        .and(db_filter.clone())
+        .and(warp::any().map(move || token_issuer.clone()))
+        .and(login_lockout)
+        .and_then(handlers::login);
+
+    let refresh_token_route = warp::path!("token" / "refresh")
+        .and(warp::post())
+        .and(handlers::json_body())
+        .and(db_filter.clone())
+        .and(warp::any().map(move || token_refresher.clone()))
+        .and_then(handlers::refresh_token);
+
+    let logout_route = warp::path!("logout")
+        .and(warp::post())
+        .and(handlers::authenticate_session(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and(handlers::optional_json_body())
+        .and_then(handlers::logout);
+
+    let revoke_user_sessions_route = warp::path!("users" / String / "sessions")
+        .and(warp::delete())
+        .and(handlers::require(
+            token_checker.clone(),
+            db.clone(),
+            Permission::RevokeSessions,
+        ))
+        .and(db_filter.clone())
+        .and_then(handlers::revoke_user_sessions);
+
+    let list_roles_route = warp::path!("roles").and(warp::get()).and_then(handlers::list_roles);
+
+    let set_user_role_route = warp::path!("users" / String / "role")
+        .and(warp::put())
+        .and(handlers::require(token_checker.clone(), db.clone(), Permission::ManageRoles))
+        .and(db_filter.clone())
+        .and(handlers::json_body())
+        .and_then(handlers::set_user_role);
+
+    let list_users_route = warp::path!("users")
+        .and(warp::get())
+        .and(handlers::require(token_checker.clone(), db.clone(), Permission::ManageUsers))
+        .and(warp::query())
+        .and(db_filter.clone())
+        .and_then(handlers::list_users);
+
+    let get_user_route = warp::path!("users" / String)
+        .and(warp::get())
+        .and(handlers::require(token_checker.clone(), db.clone(), Permission::ManageUsers))
+        .and(db_filter.clone())
+        .and_then(handlers::get_user);
+
+    let update_user_route = warp::path!("users" / String)
+        .and(warp::patch())
+        .and(handlers::require(token_checker.clone(), db.clone(), Permission::ManageUsers))
+        .and(db_filter.clone())
+        .and(handlers::json_body())
+        .and_then(handlers::update_user);
+
+    let deactivate_user_route = warp::path!("users" / String / "deactivate")
+        .and(warp::post())
+        .and(handlers::require(token_checker.clone(), db.clone(), Permission::ManageUsers))
+        .and(db_filter.clone())
+        .and_then(handlers::deactivate_user);
+
+    let reactivate_user_route = warp::path!("users" / String / "reactivate")
+        .and(warp::post())
+        .and(handlers::require(token_checker.clone(), db.clone(), Permission::ManageUsers))
+        .and(db_filter.clone())
+        .and_then(handlers::reactivate_user);
+
+    let delete_user_route = warp::path!("users" / String)
+        .and(warp::delete())
+        .and(handlers::require(token_checker.clone(), db.clone(), Permission::ManageUsers))
+        .and(db_filter.clone())
+        .and_then(handlers::delete_user);
+
+    let forgot_password_route = warp::path!("password" / "forgot")
+        .and(warp::post())
+        .and(handlers::rate_limit_ip(
+            client_ip.clone(),
+            rate_limiter.clone(),
+            "password_forgot",
+            rate_limits.mail_per_ip,
+        ))
+        .and(db_filter.clone())
+        .and(mailer.clone())
+        .and(warp::any().map(move || background_tasks.clone()))
+        .and(handlers::json_body())
+        .and_then(handlers::forgot_password);
+
+    let reset_password_route = warp::path!("password" / "reset")
+        .and(warp::post())
+        .and(db_filter.clone())
+        .and(handlers::json_body())
+        .and_then(handlers::reset_password);
+
+    let get_me_route = warp::path!("me")
+        .and(warp::get())
+        .and(handlers::authenticate(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and_then(handlers::get_me);
+
+    let update_me_route = warp::path!("me")
+        .and(warp::patch())
+        .and(handlers::authenticate(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and(handlers::json_body())
+        .and_then(handlers::update_me);
+
+    let resend_verification_route = warp::path!("me" / "verification")
+        .and(warp::post())
+        .and(handlers::rate_limit_ip(
+            client_ip.clone(),
+            rate_limiter.clone(),
+            "verification",
+            rate_limits.mail_per_ip,
+        ))
+        .and(handlers::authenticate(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and(mailer.clone())
+        .and(public_url.clone())
+        .and_then(handlers::resend_verification);
+
+    let change_password_route = warp::path!("me" / "password")
+        .and(warp::put())
+        .and(handlers::authenticate(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and(login_lockout)
+        .and(handlers::json_body())
+        .and_then(handlers::change_password);
+
+    let request_email_change_route = warp::path!("me" / "email")
+        .and(warp::put())
+        .and(handlers::rate_limit_ip(
+            client_ip.clone(),
+            rate_limiter.clone(),
+            "email_change",
+            rate_limits.mail_per_ip,
+        ))
+        .and(handlers::authenticate(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and(mailer.clone())
+        .and(login_lockout)
+        .and(handlers::json_body())
+        .and_then(handlers::request_email_change);
+
+    let confirm_email_change_route = warp::path!("me" / "email" / "confirm")
+        .and(warp::post())
+        .and(handlers::authenticate(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and(handlers::json_body())
+        .and_then(handlers::confirm_email_change);
+
+    let list_questions_route = warp::path!("questions")
+        .and(warp::get())
+        .and(warp::query())
+        .and(db_filter.clone())
+        .and_then(handlers::list_guestions);
+
+    let add_question_route = warp::path!("questions")
+        .and(warp::post())
+        .and(handlers::rate_limit_user(
+            handlers::authenticate(token_checker.clone(), db.clone()),
+            rate_limiter,
+            "questions",
+            rate_limits.questions_per_user,
+        ))
+        .and(db_filter.clone())
+        .and(content_filter.clone())
+        .and(assignment_strategy)
+        .and(verification_policy)
+        .and(handlers::json_body())
+        .and_then(handlers::add_question);
+
+    let update_question_route = warp::path!("questions" / String)
+        .and(warp::put())
+        .and(handlers::authenticate(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and(content_filter.clone())
+        .and(handlers::json_body())
+        .and_then(handlers::update_question);
+
+    let delete_question_route = warp::path!("questions" / String)
+        .and(warp::delete())
+        .and(handlers::authenticate(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and_then(handlers::delete_question);
+
+    let get_question_route = warp::path!("questions" / String)
+        .and(warp::get())
+        .and(db_filter.clone())
+        .and_then(handlers::get_question);
+
+    let change_question_status_route = warp::path!("questions" / String / "status")
+        .and(warp::post())
+        .and(handlers::authenticate(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and(handlers::json_body())
+        .and_then(handlers::change_question_status);
+
+    let list_status_history_route = warp::path!("questions" / String / "history")
+        .and(warp::get())
+        .and(db_filter.clone())
+        .and_then(handlers::list_status_history);
+
+    let assign_question_route = warp::path!("questions" / String / "assignee")
+        .and(warp::put())
+        .and(handlers::require(
+            token_checker.clone(),
+            db.clone(),
+            Permission::AssignQuestions,
+        ))
+        .and(db_filter.clone())
+        .and(handlers::json_body())
+        .and_then(handlers::assign_question);
+
+    let unassign_question_route = warp::path!("questions" / String / "assignee")
+        .and(warp::delete())
+        .and(handlers::authenticate(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and_then(handlers::unassign_question);
+
+    let claim_question_route = warp::path!("questions" / String / "claim")
+        .and(warp::post())
+        .and(handlers::require(
+            token_checker.clone(),
+            db.clone(),
+            Permission::HandleQuestions,
+        ))
+        .and(db_filter.clone())
+        .and_then(handlers::claim_question);
+
+    let my_queue_route = warp::path!("queue")
+        .and(warp::get())
+        .and(handlers::require(
+            token_checker.clone(),
+            db.clone(),
+            Permission::HandleQuestions,
+        ))
+        .and(db_filter.clone())
+        .and_then(handlers::list_my_queue);
+
+    let staff_workload_route = warp::path!("staff" / "workload")
+        .and(warp::get())
+        .and(handlers::require(
+            token_checker.clone(),
+            db.clone(),
+            Permission::AssignQuestions,
+        ))
+        .and(db_filter.clone())
+        .and_then(handlers::staff_workload);
+
+    let add_message_route = warp::path!("questions" / String / "messages")
+        .and(warp::post())
+        .and(handlers::authenticate(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and(content_filter.clone())
+        .and(handlers::json_body())
+        .and_then(handlers::add_message);
+
+    let list_messages_route = warp::path!("questions" / String / "messages")
+        .and(warp::get())
+        .and(handlers::authenticate(token_checker.clone(), db.clone()))
+        .and(db_filter.clone())
+        .and_then(handlers::list_messages);
+
+    let content_filter_status_route = warp::path!("moderation" / "status")
+        .and(warp::get())
+        .and(content_filter.clone())
+        .and_then(handlers::content_filter_status);
+
+    let list_review_queue_route = warp::path!("moderation" / "queue")
+        .and(warp::get())
+        .and(handlers::require(
+            token_checker.clone(),
+            db.clone(),
+            Permission::ReviewContent,
+        ))
+        .and(db_filter.clone())
+        .and_then(handlers::list_review_queue);
+
+    let mark_reviewed_route = warp::path!("moderation" / "queue" / String)
+        .and(warp::delete())
+        .and(handlers::require(
+            token_checker.clone(),
+            db.clone(),
+            Permission::ReviewContent,
+        ))
+        .and(db_filter.clone())
+        .and_then(handlers::mark_reviewed);
+
+    // grouped and boxed, a single `or` chain this long overflows the trait solver
+    let account_routes = handlers::named("/users", add_usr_route)
+        .or(handlers::named("/users/verify", verify_email_route))
+        .or(handlers::named("/login", login_user_route))
+        .or(handlers::named("/token/refresh", refresh_token_route))
+        .or(handlers::named("/logout", logout_route))
+        .or(handlers::named("/password/forgot", forgot_password_route))
+        .or(handlers::named("/password/reset", reset_password_route))
+        .or(handlers::named("/users/:id/sessions", revoke_user_sessions_route))
+        .or(handlers::named("/roles", list_roles_route))
+        .or(handlers::named("/users/:id/role", set_user_role_route))
+        .or(handlers::named("/users", list_users_route))
+        .or(handlers::named("/users/:id", get_user_route))
+        .or(handlers::named("/users/:id", update_user_route))
+        .or(handlers::named("/users/:id/deactivate", deactivate_user_route))
+        .or(handlers::named("/users/:id/reactivate", reactivate_user_route))
+        .or(handlers::named("/users/:id", delete_user_route))
+        .or(handlers::named("/me", get_me_route))
+        .or(handlers::named("/me", update_me_route))
+        .or(handlers::named("/me/verification", resend_verification_route))
+        .or(handlers::named("/me/password", change_password_route))
+        .or(handlers::named("/me/email", request_email_change_route))
+        .or(handlers::named("/me/email/confirm", confirm_email_change_route))
+        .boxed();
+
+    let question_routes = handlers::named("/questions", list_questions_route)
+        .or(handlers::named("/questions", add_question_route))
+        .or(handlers::named("/questions/:id", update_question_route))
+        .or(handlers::named("/questions/:id", delete_question_route))
+        .or(handlers::named("/questions/:id", get_question_route))
+        .or(handlers::named("/questions/:id/status", change_question_status_route))
+        .or(handlers::named("/questions/:id/history", list_status_history_route))
+        .or(handlers::named("/questions/:id/assignee", assign_question_route))
+        .or(handlers::named("/questions/:id/assignee", unassign_question_route))
+        .or(handlers::named("/questions/:id/claim", claim_question_route))
+        .or(handlers::named("/queue", my_queue_route))
+        .or(handlers::named("/staff/workload", staff_workload_route))
+        .or(handlers::named("/questions/:id/messages", add_message_route))
+        .or(handlers::named("/questions/:id/messages", list_messages_route))
+        .boxed();
+
+    let healthz_route = warp::path!("healthz").and(warp::get()).and_then(handlers::healthz);
+
+    let readyz_route = warp::path!("readyz")
+        .and(warp::get())
+        .and(db_filter.clone())
+        .and(content_filter.clone())
+        .and(warp::any().map(move || readiness.clone()))
+        .and_then(handlers::readyz);
+
+    let version_route = warp::path!("version").and(warp::get()).and_then(handlers::version);
+
+    let moderation_routes = handlers::named("/moderation/status", content_filter_status_route)
+        .or(handlers::named("/moderation/queue", list_review_queue_route))
+        .or(handlers::named("/moderation/queue/:id", mark_reviewed_route))
+        .boxed();
+
+    let health_routes = handlers::named("/healthz", healthz_route)
+        .or(handlers::named("/readyz", readyz_route))
+        .or(handlers::named("/version", version_route))
+        .boxed();
+
+    let api = account_routes
+        .or(question_routes)
+        .or(moderation_routes)
+        .or(health_routes)
+        .with(cors)
+        .recover(handle_err);
+    let tracked = warp::any()
+        .map(Instant::now)
+        .and(warp::method())
+        .and(api)
+        .map(handlers::track_request);
+    handlers::request_id()
+        .and(tracked)
+        .map(with_request_id)
+        .with(warp::trace(handlers::request_span))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::JWTAuth;
+    use crate::mail::Mail;
+    use crate::moderation::{BadWordsServiceOkResponse, FilterStatus};
+    use crate::ratelimit::MemoryRateLimiter;
+    use crate::storage::memory::MemoryStore;
+    use crate::types::shared::Id;
+    use error_handling::ServiceError;
+    use serde_json::{json, Value};
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+    use warp::http::StatusCode;
+
+    /// Censors "darn", and with `unchecked` lets every text through for a review instead, like an open circuit would.
+    #[derive(Clone, Debug, Default)]
+    struct FakeFilter {
+        unchecked: bool,
+    }
+
+    impl ContentFilter for FakeFilter {
+        async fn check(&self, text: String) -> Result<BadWordsServiceOkResponse, ServiceError> {
+            let censored_content = match self.unchecked {
+                true => text.clone(),
+                false => text.replace("darn", "****"),
+            };
+            Ok(BadWordsServiceOkResponse {
+                content: text,
+                bad_words_total: 0,
+                bad_words_list: Vec::new(),
+                censored_content,
+                needs_review: self.unchecked,
+            })
+        }
+
+        fn status(&self) -> FilterStatus {
+            FilterStatus::plain("fake")
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct SentMails(Arc<Mutex<Vec<Mail>>>);
+
+    impl Mailer for SentMails {
+        async fn send(&self, mail: Mail) -> Result<(), ServiceError> {
+            self.0.lock().unwrap().push(mail);
+            Ok(())
+        }
+    }
+
+    struct App<R> {
+        routes: R,
+        db: MemoryStore,
+        mails: SentMails,
+        tasks: TaskTracker,
+    }
+
+    fn app(filter: FakeFilter) -> App<impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone + 'static> {
+        let db = MemoryStore::new();
+        let mails = SentMails::default();
+        let tasks = TaskTracker::new();
+        let services = Services {
+            db: db.clone(),
+            auth: JWTAuth::new("0123456789abcdef".to_owned()),
+            content_filter: filter,
+            mailer: mails.clone(),
+            rate_limiter: MemoryRateLimiter::default(),
+            tasks: tasks.clone(),
+            readiness: Readiness::default(),
+        };
+        let settings = RouteSettings {
+            cors_origins: Vec::new(),
+            public_url: "http://localhost".to_owned(),
+            moderator_key: "modkey".to_owned(),
+            lockout: LoginLockout {
+                threshold: 3,
+                base_secs: 60,
+                max_secs: 60,
+            },
+            email_verification: VerificationPolicy::Optional,
+            auto_assign: AssignmentStrategy::Off,
+            rate_limits: RateLimits {
+                login_per_ip: None,
+                mail_per_ip: None,
+                questions_per_user: None,
+            },
+            trusted_proxies: Vec::new(),
+        };
+        App {
+            routes: api(services, settings),
+            db,
+            mails,
+            tasks,
+        }
+    }
+
+    impl<R: Filter<Extract = (impl Reply,), Error = Rejection> + Clone + 'static> App<R> {
+        async fn call(&self, method: &str, path: &str, token: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+            let mut req = warp::test::request().method(method).path(path);
+            if let Some(token) = token {
+                req = req.header("Authorization", token);
+            }
+            if let Some(body) = body {
+                req = req.json(&body);
+            }
+            let res = req.reply(&self.routes).await;
+            let body = serde_json::from_slice(res.body()).unwrap_or(Value::Null);
+            (res.status(), body)
+        }
+
+        /// Signs a user up and logs them in, returning their access and refresh tokens.
+        async fn login_as(&self, email: &str, moderator: bool) -> (String, String) {
+            let user = json!({
+                "email": email,
+                "password": "secret123",
+                "first_name": "Jane",
+                "last_name": "Doe",
+                "is_moderator": moderator,
+            });
+            let key = moderator.then_some("modkey");
+            let (status, _) = self.call("POST", "/users", key, Some(user)).await;
+            assert_eq!(status, StatusCode::CREATED);
+            let (status, body) = self
+                .call("POST", "/login", None, Some(json!({"email": email, "password": "secret123"})))
+                .await;
+            assert_eq!(status, StatusCode::CREATED);
+            (
+                body["token"].as_str().unwrap().to_owned(),
+                body["refresh_token"].as_str().unwrap().to_owned(),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn questions_are_censored_when_added_and_updated() {
+        let app = app(FakeFilter::default());
+        let (token, _) = app.login_as("jane@example.com", false).await;
+
+        let question = json!({"title": "darn printer", "content": "it is darn broken"});
+        let (status, body) = app.call("POST", "/questions", Some(&token), Some(question)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let path = format!("/questions/{}", body["_id"].as_str().unwrap());
+        let (_, body) = app.call("GET", &path, None, None).await;
+        assert_eq!(body["title"], "**** printer");
+        assert_eq!(body["content"], "it is **** broken");
+
+        let question = json!({"title": "printer", "content": "still darn broken"});
+        let (status, _) = app.call("PUT", &path, Some(&token), Some(question)).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (_, body) = app.call("GET", &path, None, None).await;
+        assert_eq!(body["title"], "printer");
+        assert_eq!(body["content"], "still **** broken");
+    }
+
+    #[tokio::test]
+    async fn unchecked_texts_are_queued_for_review() {
+        let app = app(FakeFilter { unchecked: true });
+        let (token, _) = app.login_as("jane@example.com", false).await;
+        let (moderator, _) = app.login_as("mod@example.com", true).await;
+
+        let question = json!({"title": "printer", "content": "it is broken"});
+        let (_, body) = app.call("POST", "/questions", Some(&token), Some(question)).await;
+        let path = format!("/questions/{}/messages", body["_id"].as_str().unwrap());
+        let (status, _) = app
+            .call("POST", &path, Some(&token), Some(json!({"content": "any news?"})))
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (status, _) = app.call("GET", "/moderation/queue", Some(&token), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (_, queue) = app.call("GET", "/moderation/queue", Some(&moderator), None).await;
+        let queue = queue.as_array().unwrap();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0]["title"], "printer");
+        assert_eq!(queue[1]["content"], "any news?");
+
+        let path = format!("/moderation/queue/{}", queue[0]["_id"].as_str().unwrap());
+        let (status, _) = app.call("DELETE", &path, Some(&moderator), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (_, queue) = app.call("GET", "/moderation/queue", Some(&moderator), None).await;
+        assert_eq!(queue.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn deactivated_staff_get_no_questions() {
+        let app = app(FakeFilter::default());
+        let (token, _) = app.login_as("jane@example.com", false).await;
+        let (moderator, _) = app.login_as("mod@example.com", true).await;
+        let (gone, _) = app.login_as("gone@example.com", true).await;
+        let (_, me) = app.call("GET", "/me", Some(&gone), None).await;
+        let gone_id = me["_id"].as_str().unwrap().to_owned();
+        app.db.set_user_active(Id::from_str(&gone_id).unwrap(), false).await.unwrap();
+
+        let question = json!({"title": "printer", "content": "it is broken"});
+        let (_, body) = app.call("POST", "/questions", Some(&token), Some(question)).await;
+        let path = format!("/questions/{}/assignee", body["_id"].as_str().unwrap());
+        let (status, body) = app
+            .call("PUT", &path, Some(&moderator), Some(json!({"assignee": gone_id})))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.to_string().contains("assignee must be an agent"), "{}", body);
+
+        let (_, workload) = app.call("GET", "/staff/workload", Some(&moderator), None).await;
+        let workload = workload.as_array().unwrap();
+        assert_eq!(workload.len(), 1);
+        assert_eq!(workload[0]["email"], "mod@example.com");
+    }
+
+    #[tokio::test]
+    async fn logout_revokes_the_access_and_the_refresh_token() {
+        let app = app(FakeFilter::default());
+        let (token, refresh_token) = app.login_as("jane@example.com", false).await;
+        let (status, _) = app.call("GET", "/me", Some(&token), None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let body = json!({"refresh_token": refresh_token});
+        let (status, _) = app.call("POST", "/logout", Some(&token), Some(body.clone())).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, _) = app.call("GET", "/me", Some(&token), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = app.call("POST", "/token/refresh", None, Some(body)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn logout_leaves_refresh_tokens_of_others_alone() {
+        let app = app(FakeFilter::default());
+        let (token, _) = app.login_as("jane@example.com", false).await;
+        let (_, refresh_token) = app.login_as("john@example.com", false).await;
+
+        let body = json!({"refresh_token": refresh_token});
+        let (status, _) = app.call("POST", "/logout", Some(&token), Some(body.clone())).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = app.call("POST", "/token/refresh", None, Some(body)).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn wrong_current_passwords_lock_the_account() {
+        let app = app(FakeFilter::default());
+        let (token, _) = app.login_as("jane@example.com", false).await;
+
+        let change = json!({"current_password": "guess1234", "new_password": "changed123"});
+        for _ in 0..3 {
+            let (status, _) = app.call("PUT", "/me/password", Some(&token), Some(change.clone())).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+        let change = json!({"current_password": "secret123", "new_password": "changed123"});
+        let (status, _) = app.call("PUT", "/me/password", Some(&token), Some(change)).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn password_reset_lifts_the_lockout() {
+        let app = app(FakeFilter::default());
+        app.login_as("jane@example.com", false).await;
+        let creds = json!({"email": "jane@example.com", "password": "guess1234"});
+        for _ in 0..3 {
+            app.call("POST", "/login", None, Some(creds.clone())).await;
+        }
+
+        app.call("POST", "/password/forgot", None, Some(json!({"email": "jane@example.com"})))
+            .await;
+        app.tasks.close();
+        app.tasks.wait().await;
+        let mail = app.mails.0.lock().unwrap().last().cloned().unwrap();
+        let reset = json!({"token": mail.body.lines().nth(1).unwrap(), "new_password": "changed123"});
+        let (status, _) = app.call("POST", "/password/reset", None, Some(reset)).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let creds = json!({"email": "jane@example.com", "password": "changed123"});
+        let (status, _) = app.call("POST", "/login", None, Some(creds)).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn password_reset_ends_the_sessions_and_works_once() {
+        let app = app(FakeFilter::default());
+        let (token, _) = app.login_as("jane@example.com", false).await;
+
+        let (status, _) = app
+            .call("POST", "/password/forgot", None, Some(json!({"email": "jane@example.com"})))
+            .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        app.tasks.close();
+        app.tasks.wait().await;
+        let mail = app.mails.0.lock().unwrap().last().cloned().unwrap();
+        let reset = json!({"token": mail.body.lines().nth(1).unwrap(), "new_password": "changed123"});
+
+        let (status, _) = app.call("POST", "/password/reset", None, Some(reset.clone())).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = app.call("GET", "/me", Some(&token), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = app.call("POST", "/password/reset", None, Some(reset)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let creds = json!({"email": "jane@example.com", "password": "changed123"});
+        let (status, _) = app.call("POST", "/login", None, Some(creds)).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+}
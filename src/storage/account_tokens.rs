@@ -0,0 +1,181 @@
+use crate::types::account::{AccountToken, AccountTokenPurpose};
+use error_handling::ServiceError;
+use sqlx::postgres::{PgRow, Postgres};
+use sqlx::{Executor, Row};
+use std::future::Future;
+use tracing::{event, Level};
+
+use super::base::Db;
+use super::tokens::{generate_token, revoke_sessions};
+use super::users::get_db_err_code;
+
+/// Marks the token used and returns what it was issued for. With `user_id`, tokens of other users are left alone.
+/// Unknown, expired, already used tokens and tokens issued for another purpose are all rejected alike.
+async fn consume<'c, E: Executor<'c, Database = Postgres>>(
+    executor: E,
+    token: String,
+    purpose: AccountTokenPurpose,
+    user_id: Option<&str>,
+) -> Result<AccountToken, ServiceError> {
+    let res = sqlx::query(
+        "UPDATE account_tokens SET used_at = NOW() WHERE token_hash = encode(digest($1, 'sha256'), 'hex') AND purpose = $2 AND used_at IS NULL AND expires_at > NOW() \
+        AND ($3::text IS NULL OR user_id = uuid_or_null($3)) RETURNING user_id::text, email;",
+    )
+    .bind(token)
+    .bind(purpose.to_str())
+    .bind(user_id)
+    .map(|row: PgRow| AccountToken {
+        user_id: row.get("user_id"),
+        email: row.get("email"),
+    })
+    .fetch_optional(executor)
+    .await;
+
+    match res {
+        Ok(Some(found)) => Ok(found),
+        Ok(None) => Err(ServiceError::AuthTokenMissingOrInvalid),
+        Err(e) => {
+            event!(Level::ERROR, "Consume account token query failed: {}", e);
+            Err(ServiceError::DbQueryError)
+        }
+    }
+}
+
+/// Single-use tokens mailed to users to verify their email, change it or reset their password.
+pub trait AccountTokenStore: std::fmt::Debug + Clone + Send + Sync + 'static {
+    /// Issues a single-use token for the user and returns its plain text value, only its digest is stored.
+    /// Tokens issued earlier for the same purpose stop working.
+    fn issue_account_token(
+        &self,
+        user_id: String,
+        purpose: AccountTokenPurpose,
+        email: Option<String>,
+    ) -> impl Future<Output = Result<String, ServiceError>> + Send;
+    /// Marks the token used and returns what it was issued for.
+    fn consume_account_token(
+        &self,
+        token: String,
+        purpose: AccountTokenPurpose,
+    ) -> impl Future<Output = Result<AccountToken, ServiceError>> + Send;
+    /// Consumes the user's email change token and switches to the email it was issued for, both or neither.
+    /// The new email counts as verified, confirming the change proved it works.
+    fn confirm_email_change(&self, token: String, user_id: String) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    /// Consumes the password reset token, sets the new password, lifts a lockout and ends all of the user's sessions,
+    /// all or nothing.
+    fn reset_password(&self, token: String, password: String) -> impl Future<Output = Result<(), ServiceError>> + Send;
+}
+
+impl AccountTokenStore for Db {
+    async fn issue_account_token(
+        &self,
+        user_id: String,
+        purpose: AccountTokenPurpose,
+        email: Option<String>,
+    ) -> Result<String, ServiceError> {
+        let mut tx = self.connection.begin().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to start transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+
+        let res = sqlx::query(
+            "UPDATE account_tokens SET used_at = NOW() WHERE user_id = uuid_or_null($1) AND purpose = $2 AND used_at IS NULL;",
+        )
+        .bind(&user_id)
+        .bind(purpose.to_str())
+        .execute(&mut tx)
+        .await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "Invalidate account tokens query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+
+        let token = generate_token();
+        let res = sqlx::query(
+            "INSERT INTO account_tokens (token_hash, purpose, user_id, email, expires_at) VALUES (encode(digest($1, 'sha256'), 'hex'), $2, uuid_or_null($3), $4, NOW() + make_interval(mins => $5));",
+        )
+        .bind(&token)
+        .bind(purpose.to_str())
+        .bind(&user_id)
+        .bind(email)
+        .bind(purpose.ttl_mins())
+        .execute(&mut tx)
+        .await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "Issue account token query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+
+        tx.commit().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to commit transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+        Ok(token)
+    }
+
+    async fn consume_account_token(&self, token: String, purpose: AccountTokenPurpose) -> Result<AccountToken, ServiceError> {
+        consume(&self.connection, token, purpose, None).await
+    }
+
+    async fn confirm_email_change(&self, token: String, user_id: String) -> Result<(), ServiceError> {
+        let mut tx = self.connection.begin().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to start transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+
+        let found = consume(&mut tx, token, AccountTokenPurpose::EmailChange, Some(&user_id)).await?;
+        let res = sqlx::query(
+            "UPDATE users SET email = COALESCE($1, email), email_verified_at = COALESCE(email_verified_at, NOW()) WHERE _id = uuid_or_null($2);",
+        )
+        .bind(found.email)
+        .bind(&user_id)
+        .execute(&mut tx)
+        .await;
+        match res {
+            Ok(res) if res.rows_affected() == 0 => return Err(ServiceError::ObjectNotFound),
+            Ok(_) => (),
+            Err(e) if get_db_err_code(&e).await == 23505 => {
+                event!(Level::WARN, "{}", e);
+                return Err(ServiceError::ConflictInDb);
+            }
+            Err(e) => {
+                event!(Level::ERROR, "Change email query failed: {}", e);
+                return Err(ServiceError::DbQueryError);
+            }
+        }
+
+        tx.commit().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to commit transaction: {}", e);
+            ServiceError::DbQueryError
+        })
+    }
+
+    async fn reset_password(&self, token: String, password: String) -> Result<(), ServiceError> {
+        let mut tx = self.connection.begin().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to start transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+
+        let found = consume(&mut tx, token, AccountTokenPurpose::PasswordReset, None).await?;
+        let res = sqlx::query(
+            "UPDATE users SET password = crypt($1, gen_salt('bf', 8)), failed_logins = 0, locked_until = NULL WHERE _id = uuid_or_null($2);",
+        )
+            .bind(password)
+            .bind(&found.user_id)
+            .execute(&mut tx)
+            .await;
+        match res {
+            Ok(res) if res.rows_affected() == 0 => return Err(ServiceError::ObjectNotFound),
+            Ok(_) => (),
+            Err(e) => {
+                event!(Level::ERROR, "Set password query failed: {}", e);
+                return Err(ServiceError::DbQueryError);
+            }
+        }
+        revoke_sessions(&mut tx, &found.user_id).await?;
+
+        tx.commit().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to commit transaction: {}", e);
+            ServiceError::DbQueryError
+        })
+    }
+}
@@ -1,7 +1,16 @@
 #![allow(dead_code)]
 
 use sqlx::postgres::{PgPool, PgPoolOptions};
-use std::env;
+
+#[derive(Debug, Clone)]
+pub struct DbSettings {
+    pub user: String,
+    pub password: String,
+    pub host: String,
+    pub port: u16,
+    pub name: String,
+    pub max_connections: u32,
+}
 
 #[derive(Debug, Clone)]
 pub struct Db {
@@ -15,8 +24,17 @@ impl Db {
         }
     }
 
-    async fn build(conn_string: &str) -> Self {
-        match PgPoolOptions::new().max_connections(5).connect(conn_string).await {
+    /// Closes the pool, waiting for the connections in use to be released.
+    pub async fn close(&self) {
+        self.connection.close().await
+    }
+
+    async fn build(conn_string: &str, max_connections: u32) -> Self {
+        match PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(conn_string)
+            .await
+        {
             Ok(connection) => Self { connection },
             Err(err) => panic!("Couldn't establish DB connection: {}", err),
         }
@@ -24,18 +42,14 @@ impl Db {
 
     pub async fn new(usr: &str, pass: &str, host: &str, port: &str, db_name: &str) -> Self {
         let db_string = format!("postgresql://{}:{}@{}:{}/{}", usr, pass, host, port, db_name);
-        Self::build(&db_string).await
+        Self::build(&db_string, 5).await
     }
 
-    pub async fn from_env() -> Self {
+    pub async fn from_settings(settings: &DbSettings) -> Self {
         let db_string = format!(
             "postgresql://{}:{}@{}:{}/{}",
-            &env::var("POSTGRES_USER").expect("POSTGRES_USER environment variable not set!"),
-            &env::var("POSTGRES_PASSWORD").expect("POSTGRES_PASSWORD environment variable not set!"),
-            &env::var("POSTGRES_HOST").expect("POSTGRES_HOST environment variable not set!"),
-            &env::var("POSTGRES_PORT").expect("POSTGRES_PORT environment variable not set!"),
-            &env::var("POSTGRES_DB").expect("POSTGRES_DB environment variable not set!"),
+            settings.user, settings.password, settings.host, settings.port, settings.name
         );
-        Self::build(&db_string).await
+        Self::build(&db_string, settings.max_connections).await
     }
 }
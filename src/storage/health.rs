@@ -0,0 +1,44 @@
+use error_handling::ServiceError;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::collections::HashSet;
+use std::future::Future;
+use tracing::{event, Level};
+
+use super::base::Db;
+
+pub trait HealthStore: std::fmt::Debug + Clone + Send + Sync + 'static {
+    fn ping(&self) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    /// Versions of the migrations embedded in the binary that haven't been applied (successfully) yet.
+    fn pending_migrations(&self) -> impl Future<Output = Result<Vec<i64>, ServiceError>> + Send;
+}
+
+impl HealthStore for Db {
+    async fn ping(&self) -> Result<(), ServiceError> {
+        if let Err(e) = sqlx::query("SELECT 1;").execute(&self.connection).await {
+            event!(Level::ERROR, "Ping query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(())
+    }
+
+    async fn pending_migrations(&self) -> Result<Vec<i64>, ServiceError> {
+        let res = sqlx::query("SELECT version FROM _sqlx_migrations WHERE success;")
+            .map(|row: PgRow| row.get::<i64, _>("version"))
+            .fetch_all(&self.connection)
+            .await;
+        let applied: HashSet<i64> = match res {
+            Ok(versions) => versions.into_iter().collect(),
+            Err(e) => {
+                event!(Level::ERROR, "Applied migrations query failed: {}", e);
+                return Err(ServiceError::DbQueryError);
+            }
+        };
+        Ok(sqlx::migrate!()
+            .migrations
+            .iter()
+            .filter(|m| !m.migration_type.is_down_migration() && !applied.contains(&m.version))
+            .map(|m| m.version)
+            .collect())
+    }
+}
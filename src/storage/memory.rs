@@ -0,0 +1,965 @@
+use super::account_tokens::AccountTokenStore;
+use super::health::HealthStore;
+use super::messages::MessageStore;
+use super::moderation::ReviewStore;
+use super::questions::{keyset_page, mark_snippet, QuestionStore, MARK_START, MARK_STOP};
+use super::tokens::{generate_token, TokenStore, REFRESH_TOKEN_EXP_DAYS};
+use super::users::UserStore;
+use crate::types::account::{AccountToken, AccountTokenPurpose};
+use crate::types::assignment::{AssignmentStrategy, StaffWorkloadOut};
+use crate::types::auth::{Claims, Creds, LoginLockout};
+use crate::types::message::{MsgByUser, MsgOut};
+use crate::types::moderation::{ReviewItemOut, ReviewSubject};
+use crate::types::pagination::{Keyset, KeysetPosition, Page, Pagination};
+use crate::types::query::{QuestQuery, QuestSort, TagsMatch};
+use crate::types::question::{QuestByUser, QuestOut, QuestSearchHit, QuestStatus, StatusChangeOut};
+use crate::types::role::{Permission, Role};
+use crate::types::shared::Id;
+use crate::types::user::{UserIn, UserOut, UserPatch, UserTknDetails};
+use chrono::{NaiveDateTime, Utc};
+use error_handling::ServiceError;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+#[derive(Debug)]
+struct UserRecord {
+    user: UserOut,
+    password: String,
+    failed_logins: i32,
+    locked_until: Option<Instant>,
+    /// access tokens issued before, in seconds since the epoch, are revoked
+    sessions_revoked_at: Option<f64>,
+}
+
+#[derive(Debug)]
+struct RefreshTokenRecord {
+    token: String,
+    family: String,
+    user_id: String,
+    /// rotated or revoked
+    spent: bool,
+    expires_at: Instant,
+}
+
+#[derive(Debug)]
+struct AccountTokenRecord {
+    token: String,
+    purpose: AccountTokenPurpose,
+    user_id: String,
+    email: Option<String>,
+    used: bool,
+    expires_at: Instant,
+}
+
+#[derive(Debug)]
+struct ReviewRecord {
+    _id: String,
+    created_at: String,
+    subject: ReviewSubject,
+    reviewed_by: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Tables {
+    questions: Vec<QuestOut>,
+    status_history: Vec<(String, StatusChangeOut)>,
+    /// question id -> when its current assignee got it
+    assigned_at: HashMap<String, String>,
+    users: Vec<UserRecord>,
+    review_queue: Vec<ReviewRecord>,
+    messages: Vec<MsgOut>,
+    refresh_tokens: Vec<RefreshTokenRecord>,
+    revoked_jtis: HashSet<String>,
+    account_tokens: Vec<AccountTokenRecord>,
+}
+
+impl Tables {
+    /// Like `tokens::revoke_sessions`, invalidates the user's access tokens issued so far and revokes their refresh tokens.
+    fn revoke_sessions(&mut self, user_id: &str) -> Result<(), ServiceError> {
+        let record = self
+            .users
+            .iter_mut()
+            .find(|record| record.user._id == user_id)
+            .ok_or(ServiceError::ObjectNotFound)?;
+        record.sessions_revoked_at = Some(unix_now());
+        for token in self.refresh_tokens.iter_mut().filter(|token| token.user_id == user_id) {
+            token.spent = true;
+        }
+        Ok(())
+    }
+
+    /// Finds the account token `consume_account_token` would mark used, leaving it to the caller to do so.
+    fn usable_account_token(
+        &self,
+        token: &str,
+        purpose: AccountTokenPurpose,
+        user_id: Option<&str>,
+    ) -> Result<usize, ServiceError> {
+        let now = Instant::now();
+        self.account_tokens
+            .iter()
+            .position(|record| {
+                record.token == token
+                    && record.purpose == purpose
+                    && !record.used
+                    && record.expires_at > now
+                    && user_id.map(|user_id| record.user_id == user_id).unwrap_or(true)
+            })
+            .ok_or(ServiceError::AuthTokenMissingOrInvalid)
+    }
+}
+
+/// All of the stores kept in process memory, meant for tests.
+/// Nothing is persisted and passwords are stored as is, so never use it in production.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    tables: Arc<RwLock<Tables>>,
+}
+
+fn now() -> String {
+    // same text representation Postgres gives for `created_at::text`
+    Utc::now().naive_utc().format("%Y-%m-%d %H:%M:%S%.6f").to_string()
+}
+
+fn unix_now() -> f64 {
+    Utc::now().timestamp_micros() as f64 / 1e6
+}
+
+fn parse_created_at(created_at: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(created_at, "%Y-%m-%d %H:%M:%S%.f").unwrap_or_default()
+}
+
+fn matches(query: &QuestQuery, q: &QuestOut) -> bool {
+    if !query.status.is_empty() && !query.status.contains(&q.status) {
+        return false;
+    }
+    if !query.tags.is_empty() {
+        let tags = q.tags.clone().unwrap_or_default();
+        let matched = match query.tags_match.unwrap_or(TagsMatch::Any) {
+            TagsMatch::Any => query.tags.iter().any(|tag| tags.contains(tag)),
+            TagsMatch::All => query.tags.iter().all(|tag| tags.contains(tag)),
+        };
+        if !matched {
+            return false;
+        }
+    }
+    if query.author.as_ref().map(|author| author != &q.author).unwrap_or(false) {
+        return false;
+    }
+    let created_at = parse_created_at(&q.created_at);
+    if query.created_after.map(|after| created_at < after).unwrap_or(false) {
+        return false;
+    }
+    if query.created_before.map(|before| created_at >= before).unwrap_or(false) {
+        return false;
+    }
+    true
+}
+
+fn is_open(q: &QuestOut) -> bool {
+    matches!(q.status, QuestStatus::Pending | QuestStatus::Unresolved)
+}
+
+fn search_terms(query: &QuestQuery) -> Vec<String> {
+    query
+        .search
+        .clone()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|term| term.to_lowercase())
+        .collect()
+}
+
+/// Like `websearch_to_tsquery`, every term has to be present somewhere in the question.
+fn is_hit(q: &QuestOut, terms: &[String]) -> bool {
+    let text = format!("{} {}", q.title, q.content).to_lowercase();
+    terms.iter().all(|term| text.contains(term.as_str()))
+}
+
+fn count_hits(text: &str, terms: &[String]) -> usize {
+    let text = text.to_lowercase();
+    terms.iter().map(|term| text.matches(term.as_str()).count()).sum()
+}
+
+fn highlight(text: &str, terms: &[String]) -> String {
+    let snippet = text
+        .replace([MARK_START, MARK_STOP], "")
+        .split(' ')
+        .map(
+            |word| match terms.iter().any(|term| word.to_lowercase().contains(term.as_str())) {
+                true => format!("{}{}{}", MARK_START, word, MARK_STOP),
+                false => word.to_string(),
+            },
+        )
+        .collect::<Vec<String>>()
+        .join(" ");
+    mark_snippet(&snippet)
+}
+
+fn sort_questions(questions: &mut [QuestOut], sort: QuestSort) {
+    let by_created_at = |a: &QuestOut, b: &QuestOut| (&a.created_at, &a._id).cmp(&(&b.created_at, &b._id));
+    questions.sort_by(|a, b| match sort {
+        QuestSort::CreatedAtAsc => by_created_at(a, b),
+        QuestSort::CreatedAtDesc => by_created_at(b, a),
+        QuestSort::StatusAsc => a.status.cmp(&b.status).then_with(|| by_created_at(b, a)),
+        QuestSort::StatusDesc => b.status.cmp(&a.status).then_with(|| by_created_at(b, a)),
+    });
+}
+
+fn paginate<T>(items: Vec<T>, query: &QuestQuery) -> Vec<T> {
+    let skip = query.pagination.offset.max(0) as usize;
+    match query.pagination.limit {
+        Some(limit) => items.into_iter().skip(skip).take(limit.max(0) as usize).collect(),
+        None => items.into_iter().skip(skip).collect(),
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn filtered(&self, query: &QuestQuery) -> Vec<QuestOut> {
+        let tables = self.tables.read().unwrap();
+        tables.questions.iter().filter(|q| matches(query, q)).cloned().collect()
+    }
+}
+
+impl QuestionStore for MemoryStore {
+    async fn list_questions(&self, query: &QuestQuery) -> Result<Vec<QuestOut>, ServiceError> {
+        let mut questions = self.filtered(query);
+        if let Some(sort) = query.sort {
+            sort_questions(&mut questions, sort);
+        }
+        Ok(paginate(questions, query))
+    }
+
+    async fn list_questions_page(&self, query: &QuestQuery, keyset: &Keyset) -> Result<Page<QuestOut>, ServiceError> {
+        let mut questions = self.filtered(query);
+        let key = |q: &QuestOut| (q.created_at.clone(), q._id.clone());
+        questions = match &keyset.position {
+            KeysetPosition::First => questions,
+            KeysetPosition::After(cursor) => questions
+                .into_iter()
+                .filter(|q| key(q) > (cursor.created_at.clone(), cursor.id.clone()))
+                .collect(),
+            KeysetPosition::Before(cursor) => questions
+                .into_iter()
+                .filter(|q| key(q) < (cursor.created_at.clone(), cursor.id.clone()))
+                .collect(),
+        };
+        match keyset.position {
+            KeysetPosition::Before(_) => sort_questions(&mut questions, QuestSort::CreatedAtDesc),
+            _ => sort_questions(&mut questions, QuestSort::CreatedAtAsc),
+        }
+        questions.truncate(keyset.limit as usize + 1);
+        Ok(keyset_page(questions, keyset))
+    }
+
+    async fn count_questions(&self, query: &QuestQuery) -> Result<i64, ServiceError> {
+        let terms = search_terms(query);
+        let count = self
+            .filtered(query)
+            .iter()
+            .filter(|q| terms.is_empty() || is_hit(q, &terms))
+            .count();
+        Ok(count as i64)
+    }
+
+    async fn search_questions(&self, query: &QuestQuery) -> Result<Vec<QuestSearchHit>, ServiceError> {
+        let terms = search_terms(query);
+        let mut hits: Vec<QuestSearchHit> = self
+            .filtered(query)
+            .into_iter()
+            .filter(|q| is_hit(q, &terms))
+            .map(|q| {
+                let rank = 2 * count_hits(&q.title, &terms) + count_hits(&q.content, &terms);
+                QuestSearchHit {
+                    rank: rank as f32,
+                    title_snippet: highlight(&q.title, &terms),
+                    content_snippet: highlight(&q.content, &terms),
+                    question: q,
+                }
+            })
+            .collect();
+        match query.sort {
+            Some(sort) => {
+                let mut questions: Vec<QuestOut> = hits.iter().map(|hit| hit.question.clone()).collect();
+                sort_questions(&mut questions, sort);
+                let order: Vec<String> = questions.into_iter().map(|q| q._id).collect();
+                hits.sort_by_key(|hit| order.iter().position(|id| id == &hit.question._id));
+            }
+            None => hits.sort_by(|a, b| {
+                b.rank
+                    .partial_cmp(&a.rank)
+                    .unwrap_or(Ordering::Equal)
+                    .then_with(|| b.question.created_at.cmp(&a.question.created_at))
+            }),
+        }
+        Ok(paginate(hits, query))
+    }
+
+    async fn add_question(&self, q: QuestByUser) -> Result<Id, ServiceError> {
+        let status = QuestStatus::from_str(&q.parse_status()).map_err(|_| ServiceError::DbQueryError)?;
+        let id = Uuid::new_v4().to_string();
+        self.tables.write().unwrap().questions.push(QuestOut {
+            _id: id.clone(),
+            created_at: now(),
+            title: q.title,
+            content: q.content,
+            tags: q.tags,
+            status,
+            author: q.user_id,
+            assignee: None,
+        });
+        Ok(Id::from_str(&id).unwrap())
+    }
+
+    async fn update_question(
+        &self,
+        id: Id,
+        q: QuestByUser,
+        force: bool,
+        status_change: Option<(QuestStatus, QuestStatus)>,
+    ) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let question = tables
+            .questions
+            .iter_mut()
+            .find(|quest| quest._id == id.to_str() && (force || quest.author == q.user_id))
+            .ok_or(ServiceError::ObjectNotFound)?;
+        if let Some((from, _)) = status_change.filter(|(from, _)| question.status != *from) {
+            return Err(ServiceError::InvalidStatusTransition(format!(
+                "question is no longer {}",
+                from.to_str()
+            )));
+        }
+        question.title = q.title;
+        question.content = q.content;
+        question.tags = q.tags;
+        if let Some((from, to)) = status_change {
+            question.status = to;
+            tables.status_history.push((
+                id.to_str(),
+                StatusChangeOut {
+                    _id: Uuid::new_v4().to_string(),
+                    created_at: now(),
+                    actor: Some(q.user_id),
+                    old_status: from,
+                    new_status: to,
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    async fn delete_question(&self, id: Id, user_id: String, force: bool) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let idx = tables
+            .questions
+            .iter()
+            .position(|quest| quest._id == id.to_str() && (force || quest.author == user_id))
+            .ok_or(ServiceError::ObjectNotFound)?;
+        let removed = tables.questions.remove(idx);
+        tables.status_history.retain(|(question_id, _)| question_id != &removed._id);
+        tables.assigned_at.remove(&removed._id);
+        Ok(())
+    }
+
+    async fn get_question(&self, id: Id) -> Result<QuestOut, ServiceError> {
+        let tables = self.tables.read().unwrap();
+        tables
+            .questions
+            .iter()
+            .find(|quest| quest._id == id.to_str())
+            .cloned()
+            .ok_or(ServiceError::ObjectNotFound)
+    }
+
+    async fn change_question_status(
+        &self,
+        id: Id,
+        from: QuestStatus,
+        to: QuestStatus,
+        actor_id: String,
+    ) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let question = tables
+            .questions
+            .iter_mut()
+            .find(|quest| quest._id == id.to_str())
+            .ok_or(ServiceError::ObjectNotFound)?;
+        if question.status != from {
+            return Err(ServiceError::InvalidStatusTransition(format!(
+                "question is no longer {}",
+                from.to_str()
+            )));
+        }
+        question.status = to;
+        tables.status_history.push((
+            id.to_str(),
+            StatusChangeOut {
+                _id: Uuid::new_v4().to_string(),
+                created_at: now(),
+                actor: Some(actor_id),
+                old_status: from,
+                new_status: to,
+            },
+        ));
+        Ok(())
+    }
+
+    async fn list_status_history(&self, id: Id) -> Result<Vec<StatusChangeOut>, ServiceError> {
+        let tables = self.tables.read().unwrap();
+        Ok(tables
+            .status_history
+            .iter()
+            .filter(|(question_id, _)| question_id == &id.to_str())
+            .map(|(_, change)| change.clone())
+            .collect())
+    }
+
+    async fn assign_question(&self, id: Id, from: Option<String>, to: Option<String>) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let question = tables
+            .questions
+            .iter_mut()
+            .find(|quest| quest._id == id.to_str() && quest.assignee == from)
+            .ok_or(ServiceError::ConcurrentModification)?;
+        question.assignee = to.clone();
+        match to {
+            Some(_) => tables.assigned_at.insert(id.to_str(), now()),
+            None => tables.assigned_at.remove(&id.to_str()),
+        };
+        Ok(())
+    }
+
+    async fn list_assigned_questions(&self, assignee: String) -> Result<Vec<QuestOut>, ServiceError> {
+        let tables = self.tables.read().unwrap();
+        let mut questions: Vec<QuestOut> = tables
+            .questions
+            .iter()
+            .filter(|quest| quest.assignee.as_ref() == Some(&assignee) && is_open(quest))
+            .cloned()
+            .collect();
+        sort_questions(&mut questions, QuestSort::CreatedAtAsc);
+        Ok(questions)
+    }
+
+    async fn staff_workload(&self) -> Result<Vec<StaffWorkloadOut>, ServiceError> {
+        let tables = self.tables.read().unwrap();
+        let mut workload: Vec<StaffWorkloadOut> = tables
+            .users
+            .iter()
+            .filter(|record| record.user.role.can(Permission::HandleQuestions) && record.user.deactivated_at.is_none())
+            .map(|record| {
+                let assigned: Vec<&QuestOut> = tables
+                    .questions
+                    .iter()
+                    .filter(|quest| quest.assignee.as_ref() == Some(&record.user._id))
+                    .collect();
+                StaffWorkloadOut {
+                    _id: record.user._id.clone(),
+                    email: record.user.email.clone(),
+                    first_name: record.user.first_name.clone(),
+                    last_name: record.user.last_name.clone(),
+                    open_questions: assigned.iter().filter(|quest| is_open(quest)).count() as i64,
+                    assigned_questions: assigned.len() as i64,
+                }
+            })
+            .collect();
+        workload.sort_by_key(|staff| std::cmp::Reverse(staff.open_questions));
+        Ok(workload)
+    }
+
+    async fn pick_assignee(&self, strategy: AssignmentStrategy) -> Result<Option<String>, ServiceError> {
+        if strategy == AssignmentStrategy::Off {
+            return Ok(None);
+        }
+        let workload = self.staff_workload().await?;
+        let tables = self.tables.read().unwrap();
+        let last_assigned_at = |staff_id: &String| {
+            tables
+                .questions
+                .iter()
+                .filter(|quest| quest.assignee.as_ref() == Some(staff_id))
+                .filter_map(|quest| tables.assigned_at.get(&quest._id))
+                .max()
+                .cloned()
+        };
+        let picked = workload.iter().min_by(|a, b| {
+            let by_load = match strategy {
+                AssignmentStrategy::LeastLoaded => a.open_questions.cmp(&b.open_questions),
+                _ => Ordering::Equal,
+            };
+            // `None` sorts first, just like `NULLS FIRST`
+            by_load.then_with(|| last_assigned_at(&a._id).cmp(&last_assigned_at(&b._id)))
+        });
+        Ok(picked.map(|staff| staff._id.clone()))
+    }
+
+    async fn queue_for_review(&self, subject: ReviewSubject) -> Result<(), ServiceError> {
+        self.tables.write().unwrap().review_queue.push(ReviewRecord {
+            _id: Uuid::new_v4().to_string(),
+            created_at: now(),
+            subject,
+            reviewed_by: None,
+        });
+        Ok(())
+    }
+}
+
+impl UserStore for MemoryStore {
+    async fn add_user(&self, u: UserIn) -> Result<Id, ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        if tables.users.iter().any(|record| record.user.email == u.email) {
+            return Err(ServiceError::ConflictInDb);
+        }
+        let id = Uuid::new_v4().to_string();
+        let role = u.role();
+        tables.users.push(UserRecord {
+            user: UserOut {
+                _id: id.clone(),
+                created_at: now(),
+                email: u.email,
+                first_name: u.first_name,
+                last_name: u.last_name,
+                role,
+                deactivated_at: None,
+                email_verified_at: None,
+            },
+            password: u.password,
+            failed_logins: 0,
+            locked_until: None,
+            sessions_revoked_at: None,
+        });
+        Ok(Id::from_str(&id).unwrap())
+    }
+
+    async fn get_user_by_creds(&self, creds: Creds, lockout: LoginLockout) -> Result<UserOut, ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let record = tables
+            .users
+            .iter_mut()
+            .find(|record| record.user.email == creds.email && record.user.deactivated_at.is_none())
+            .ok_or(ServiceError::ObjectNotFound)?;
+        let now = Instant::now();
+        if let Some(until) = record.locked_until.filter(|until| *until > now) {
+            return Err(ServiceError::AccountLocked((until - now).as_secs_f64().ceil() as u64));
+        }
+
+        if record.password == creds.password {
+            record.failed_logins = 0;
+            record.locked_until = None;
+            return Ok(record.user.clone());
+        }
+        record.failed_logins += 1;
+        if let Some(secs) = lockout.lock_secs(record.failed_logins) {
+            record.locked_until = Some(now + Duration::from_secs(secs as u64));
+        }
+        Err(ServiceError::ObjectNotFound)
+    }
+
+    async fn get_user(&self, id: Id) -> Result<UserOut, ServiceError> {
+        let tables = self.tables.read().unwrap();
+        tables
+            .users
+            .iter()
+            .find(|record| record.user._id == id.to_str())
+            .map(|record| record.user.clone())
+            .ok_or(ServiceError::ObjectNotFound)
+    }
+
+    async fn set_user_role(&self, id: Id, role: Role) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let record = tables
+            .users
+            .iter_mut()
+            .find(|record| record.user._id == id.to_str())
+            .ok_or(ServiceError::ObjectNotFound)?;
+        record.user.role = role;
+        Ok(())
+    }
+
+    async fn bootstrap_admin(&self, email: String) -> Result<bool, ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        if tables.users.iter().any(|record| record.user.role == Role::Admin) {
+            return Ok(false);
+        }
+        match tables
+            .users
+            .iter_mut()
+            .find(|record| record.user.email == email && record.user.deactivated_at.is_none())
+        {
+            Some(record) => {
+                record.user.role = Role::Admin;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn list_users(&self, pagination: &Pagination) -> Result<Vec<UserOut>, ServiceError> {
+        let tables = self.tables.read().unwrap();
+        let users = tables
+            .users
+            .iter()
+            .map(|record| record.user.clone())
+            .skip(pagination.offset.max(0) as usize);
+        Ok(match pagination.limit {
+            Some(limit) => users.take(limit.max(0) as usize).collect(),
+            None => users.collect(),
+        })
+    }
+
+    async fn update_user(&self, id: Id, patch: UserPatch) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        if let Some(email) = &patch.email {
+            if tables
+                .users
+                .iter()
+                .any(|record| &record.user.email == email && record.user._id != id.to_str())
+            {
+                return Err(ServiceError::ConflictInDb);
+            }
+        }
+        let record = tables
+            .users
+            .iter_mut()
+            .find(|record| record.user._id == id.to_str())
+            .ok_or(ServiceError::ObjectNotFound)?;
+        if let Some(email) = patch.email.filter(|email| email != &record.user.email) {
+            record.user.email = email;
+            record.user.email_verified_at = None;
+        }
+        if let Some(first_name) = patch.first_name {
+            record.user.first_name = first_name;
+        }
+        if let Some(last_name) = patch.last_name {
+            record.user.last_name = last_name;
+        }
+        Ok(())
+    }
+
+    async fn set_user_active(&self, id: Id, active: bool) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let record = tables
+            .users
+            .iter_mut()
+            .find(|record| record.user._id == id.to_str())
+            .ok_or(ServiceError::ObjectNotFound)?;
+        record.user.deactivated_at = match active {
+            true => None,
+            false => record.user.deactivated_at.take().or_else(|| Some(now())),
+        };
+        Ok(())
+    }
+
+    async fn delete_user(&self, id: Id) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        if tables.questions.iter().any(|quest| quest.author == id.to_str()) {
+            return Err(ServiceError::ConflictInDb);
+        }
+        let idx = tables
+            .users
+            .iter()
+            .position(|record| record.user._id == id.to_str())
+            .ok_or(ServiceError::ObjectNotFound)?;
+        tables.users.remove(idx);
+        for quest in tables.questions.iter_mut() {
+            if quest.assignee == Some(id.to_str()) {
+                quest.assignee = None;
+            }
+        }
+        Ok(())
+    }
+
+    async fn change_password(&self, id: Id, current_password: String, new_password: String) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let record = tables
+            .users
+            .iter_mut()
+            .find(|record| record.user._id == id.to_str() && record.password == current_password)
+            .ok_or(ServiceError::AuthCredsMissing)?;
+        record.password = new_password;
+        Ok(())
+    }
+
+    async fn get_active_user_by_email(&self, email: String) -> Result<UserOut, ServiceError> {
+        let tables = self.tables.read().unwrap();
+        tables
+            .users
+            .iter()
+            .find(|record| record.user.email == email && record.user.deactivated_at.is_none())
+            .map(|record| record.user.clone())
+            .ok_or(ServiceError::ObjectNotFound)
+    }
+
+    async fn is_email_taken(&self, email: String) -> Result<bool, ServiceError> {
+        let tables = self.tables.read().unwrap();
+        Ok(tables.users.iter().any(|record| record.user.email == email))
+    }
+
+    async fn mark_email_verified(&self, id: Id) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let record = tables
+            .users
+            .iter_mut()
+            .find(|record| record.user._id == id.to_str())
+            .ok_or(ServiceError::ObjectNotFound)?;
+        record.user.email_verified_at = record.user.email_verified_at.take().or_else(|| Some(now()));
+        Ok(())
+    }
+}
+
+impl TokenStore for MemoryStore {
+    async fn issue_refresh_token(&self, user_id: String, family: Option<String>) -> Result<String, ServiceError> {
+        let token = generate_token();
+        self.tables.write().unwrap().refresh_tokens.push(RefreshTokenRecord {
+            token: token.clone(),
+            family: family.unwrap_or_else(|| Uuid::new_v4().to_string()),
+            user_id,
+            spent: false,
+            expires_at: Instant::now() + Duration::from_secs(REFRESH_TOKEN_EXP_DAYS as u64 * 24 * 60 * 60),
+        });
+        Ok(token)
+    }
+
+    async fn rotate_refresh_token(&self, token: String) -> Result<(UserTknDetails, String), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let record = tables
+            .refresh_tokens
+            .iter_mut()
+            .find(|record| record.token == token)
+            .ok_or(ServiceError::AuthTokenMissingOrInvalid)?;
+        if record.spent {
+            let family = record.family.clone();
+            for record in tables.refresh_tokens.iter_mut().filter(|record| record.family == family) {
+                record.spent = true;
+            }
+            return Err(ServiceError::AuthTokenMissingOrInvalid);
+        }
+        if record.expires_at <= Instant::now() {
+            return Err(ServiceError::AuthTokenMissingOrInvalid);
+        }
+        record.spent = true;
+        let (family, user_id) = (record.family.clone(), record.user_id.clone());
+        let user = tables
+            .users
+            .iter()
+            .find(|record| record.user._id == user_id)
+            .map(|record| UserTknDetails {
+                _id: user_id.clone(),
+                role: record.user.role,
+            })
+            .ok_or(ServiceError::AuthTokenMissingOrInvalid)?;
+
+        let new_token = generate_token();
+        tables.refresh_tokens.push(RefreshTokenRecord {
+            token: new_token.clone(),
+            family,
+            user_id,
+            spent: false,
+            expires_at: Instant::now() + Duration::from_secs(REFRESH_TOKEN_EXP_DAYS as u64 * 24 * 60 * 60),
+        });
+        Ok((user, new_token))
+    }
+
+    async fn revoke_refresh_token_family(&self, token: String, user_id: String) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let family = tables
+            .refresh_tokens
+            .iter()
+            .find(|record| record.token == token && record.user_id == user_id)
+            .map(|record| record.family.clone());
+        for record in tables
+            .refresh_tokens
+            .iter_mut()
+            .filter(|record| Some(&record.family) == family.as_ref())
+        {
+            record.spent = true;
+        }
+        Ok(())
+    }
+
+    async fn revoke_token(&self, claims: &Claims) -> Result<(), ServiceError> {
+        self.tables.write().unwrap().revoked_jtis.insert(claims.jti.clone());
+        Ok(())
+    }
+
+    async fn revoke_user_sessions(&self, user_id: String) -> Result<(), ServiceError> {
+        self.tables.write().unwrap().revoke_sessions(&user_id)
+    }
+
+    async fn is_token_revoked(&self, claims: &Claims) -> Result<bool, ServiceError> {
+        let tables = self.tables.read().unwrap();
+        if tables.revoked_jtis.contains(&claims.jti) {
+            return Ok(true);
+        }
+        Ok(tables.users.iter().any(|record| {
+            record.user._id == claims.sub
+                && (record.sessions_revoked_at.map(|at| at > claims.iat).unwrap_or(false) || record.user.deactivated_at.is_some())
+        }))
+    }
+}
+
+impl AccountTokenStore for MemoryStore {
+    async fn issue_account_token(
+        &self,
+        user_id: String,
+        purpose: AccountTokenPurpose,
+        email: Option<String>,
+    ) -> Result<String, ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        for record in tables
+            .account_tokens
+            .iter_mut()
+            .filter(|record| record.user_id == user_id && record.purpose == purpose)
+        {
+            record.used = true;
+        }
+        let token = generate_token();
+        tables.account_tokens.push(AccountTokenRecord {
+            token: token.clone(),
+            purpose,
+            user_id,
+            email,
+            used: false,
+            expires_at: Instant::now() + Duration::from_secs(purpose.ttl_mins() as u64 * 60),
+        });
+        Ok(token)
+    }
+
+    async fn consume_account_token(&self, token: String, purpose: AccountTokenPurpose) -> Result<AccountToken, ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let idx = tables.usable_account_token(&token, purpose, None)?;
+        let record = &mut tables.account_tokens[idx];
+        record.used = true;
+        Ok(AccountToken {
+            user_id: record.user_id.clone(),
+            email: record.email.clone(),
+        })
+    }
+
+    async fn confirm_email_change(&self, token: String, user_id: String) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let idx = tables.usable_account_token(&token, AccountTokenPurpose::EmailChange, Some(&user_id))?;
+        let email = tables.account_tokens[idx].email.clone();
+        if let Some(email) = &email {
+            if tables
+                .users
+                .iter()
+                .any(|record| &record.user.email == email && record.user._id != user_id)
+            {
+                return Err(ServiceError::ConflictInDb);
+            }
+        }
+        let record = tables
+            .users
+            .iter_mut()
+            .find(|record| record.user._id == user_id)
+            .ok_or(ServiceError::ObjectNotFound)?;
+        if let Some(email) = email {
+            record.user.email = email;
+        }
+        record.user.email_verified_at = record.user.email_verified_at.take().or_else(|| Some(now()));
+        tables.account_tokens[idx].used = true;
+        Ok(())
+    }
+
+    async fn reset_password(&self, token: String, password: String) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let idx = tables.usable_account_token(&token, AccountTokenPurpose::PasswordReset, None)?;
+        let user_id = tables.account_tokens[idx].user_id.clone();
+        let record = tables
+            .users
+            .iter_mut()
+            .find(|record| record.user._id == user_id)
+            .ok_or(ServiceError::ObjectNotFound)?;
+        record.password = password;
+        record.failed_logins = 0;
+        record.locked_until = None;
+        tables.revoke_sessions(&user_id)?;
+        tables.account_tokens[idx].used = true;
+        Ok(())
+    }
+}
+
+impl MessageStore for MemoryStore {
+    async fn add_message(&self, m: MsgByUser) -> Result<Id, ServiceError> {
+        let id = Uuid::new_v4().to_string();
+        self.tables.write().unwrap().messages.push(MsgOut {
+            _id: id.clone(),
+            created_at: now(),
+            content: m.content,
+            kind: m.kind,
+            question: m.question_id,
+            author: m.user_id,
+        });
+        Ok(Id::from_str(&id).unwrap())
+    }
+
+    async fn list_messages(&self, question_id: Id) -> Result<Vec<MsgOut>, ServiceError> {
+        let tables = self.tables.read().unwrap();
+        Ok(tables
+            .messages
+            .iter()
+            .filter(|m| m.question == question_id.to_str())
+            .cloned()
+            .collect())
+    }
+}
+
+impl ReviewStore for MemoryStore {
+    async fn list_review_queue(&self) -> Result<Vec<ReviewItemOut>, ServiceError> {
+        let tables = self.tables.read().unwrap();
+        let question = |id: &str| tables.questions.iter().find(|quest| quest._id == id);
+        Ok(tables
+            .review_queue
+            .iter()
+            .filter(|item| item.reviewed_by.is_none())
+            .filter_map(|item| {
+                let (quest, message) = match &item.subject {
+                    ReviewSubject::Question(id) => (question(&id.to_str())?, None),
+                    ReviewSubject::Message(id) => {
+                        let message = tables.messages.iter().find(|m| m._id == id.to_str())?;
+                        (question(&message.question)?, Some(message))
+                    }
+                };
+                Some(ReviewItemOut {
+                    _id: item._id.clone(),
+                    created_at: item.created_at.clone(),
+                    question: Some(quest._id.clone()),
+                    message: message.map(|m| m._id.clone()),
+                    title: message.is_none().then(|| quest.title.clone()),
+                    content: message.map(|m| m.content.clone()).unwrap_or_else(|| quest.content.clone()),
+                })
+            })
+            .collect())
+    }
+
+    async fn mark_reviewed(&self, id: Id, reviewer_id: String) -> Result<(), ServiceError> {
+        let mut tables = self.tables.write().unwrap();
+        let item = tables
+            .review_queue
+            .iter_mut()
+            .find(|item| item._id == id.to_str() && item.reviewed_by.is_none())
+            .ok_or(ServiceError::ObjectNotFound)?;
+        item.reviewed_by = Some(reviewer_id);
+        Ok(())
+    }
+}
+
+impl HealthStore for MemoryStore {
+    async fn ping(&self) -> Result<(), ServiceError> {
+        Ok(())
+    }
+
+    async fn pending_migrations(&self) -> Result<Vec<i64>, ServiceError> {
+        Ok(Vec::new())
+    }
+}
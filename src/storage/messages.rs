@@ -0,0 +1,58 @@
+use crate::types::message::{MsgByUser, MsgOut, MsgType};
+use crate::types::shared::Id;
+use error_handling::ServiceError;
+use std::future::Future;
+use std::str::FromStr;
+use tracing::{event, Level};
+
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+use super::base::Db;
+
+pub trait MessageStore: std::fmt::Debug + Clone + Send + Sync + 'static {
+    fn add_message(&self, m: MsgByUser) -> impl Future<Output = Result<Id, ServiceError>> + Send;
+    fn list_messages(&self, question_id: Id) -> impl Future<Output = Result<Vec<MsgOut>, ServiceError>> + Send;
+}
+
+impl MessageStore for Db {
+    async fn add_message(&self, m: MsgByUser) -> Result<Id, ServiceError> {
+        let res = sqlx::query(
+            "INSERT INTO messages (content, kind, question, author) VALUES ($1, $2::msg_type, uuid_or_null($3), uuid_or_null($4)) RETURNING _id::text;",
+        )
+        .bind(m.content)
+        .bind(m.kind.to_str())
+        .bind(m.question_id)
+        .bind(m.user_id)
+        .map(|row: PgRow| Id::from_str(row.get("_id")).unwrap())
+        .fetch_one(&self.connection)
+        .await;
+
+        if let Err(e) = res {
+            event!(Level::ERROR, "Add message query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(res.unwrap())
+    }
+
+    async fn list_messages(&self, question_id: Id) -> Result<Vec<MsgOut>, ServiceError> {
+        let q = sqlx::query(
+            "SELECT _id::text, created_at::text, content, kind::text, question::text, author::text FROM messages WHERE question = uuid_or_null($1) ORDER BY created_at, id;",
+        )
+        .bind(question_id.to_str());
+        let q = q.map(|row: PgRow| MsgOut {
+            _id: row.get("_id"),
+            created_at: row.get("created_at"),
+            content: row.get("content"),
+            kind: MsgType::from_str(row.get("kind")).unwrap(),
+            question: row.get("question"),
+            author: row.get("author"),
+        });
+        let res = q.fetch_all(&self.connection).await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "List messages query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(res.unwrap())
+    }
+}
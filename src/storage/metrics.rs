@@ -0,0 +1,20 @@
+use error_handling::ServiceError;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use tracing::{event, Level};
+
+use super::base::Db;
+
+impl Db {
+    pub async fn count_questions_by_status(&self) -> Result<Vec<(String, i64)>, ServiceError> {
+        let res = sqlx::query("SELECT status::text, COUNT(*) AS total FROM questions GROUP BY status;")
+            .map(|row: PgRow| (row.get("status"), row.get("total")))
+            .fetch_all(&self.connection)
+            .await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "Count questions by status query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(res.unwrap())
+    }
+}
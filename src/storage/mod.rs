@@ -1,5 +1,21 @@
+mod account_tokens;
 mod base;
+mod health;
+#[cfg(test)]
+pub mod memory;
+mod messages;
+mod metrics;
+mod moderation;
 mod questions;
+mod rate_limits;
+mod tokens;
 mod users;
 
+pub use account_tokens::AccountTokenStore;
 pub use base::*;
+pub use health::HealthStore;
+pub use messages::MessageStore;
+pub use moderation::ReviewStore;
+pub use questions::QuestionStore;
+pub use tokens::TokenStore;
+pub use users::UserStore;
@@ -0,0 +1,60 @@
+use crate::types::moderation::ReviewItemOut;
+use crate::types::shared::Id;
+use error_handling::ServiceError;
+use std::future::Future;
+use tracing::{event, Level};
+
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+use super::base::Db;
+
+/// The moderation queue of texts accepted without a content check, filled through `QuestionStore::queue_for_review`.
+pub trait ReviewStore: std::fmt::Debug + Clone + Send + Sync + 'static {
+    fn list_review_queue(&self) -> impl Future<Output = Result<Vec<ReviewItemOut>, ServiceError>> + Send;
+    fn mark_reviewed(&self, id: Id, reviewer_id: String) -> impl Future<Output = Result<(), ServiceError>> + Send;
+}
+
+impl ReviewStore for Db {
+    async fn list_review_queue(&self) -> Result<Vec<ReviewItemOut>, ServiceError> {
+        let q = sqlx::query(
+            "SELECT mq._id::text, mq.created_at::text, COALESCE(mq.question, m.question)::text AS question, mq.message::text, \
+            q.title, COALESCE(q.content, m.content, mq.content) AS content FROM moderation_queue mq \
+            LEFT JOIN questions q ON q._id = mq.question LEFT JOIN messages m ON m._id = mq.message \
+            WHERE mq.reviewed_at IS NULL ORDER BY mq.created_at, mq.id;",
+        )
+        .map(|row: PgRow| ReviewItemOut {
+            _id: row.get("_id"),
+            created_at: row.get("created_at"),
+            question: row.get("question"),
+            message: row.get("message"),
+            title: row.get("title"),
+            content: row.get("content"),
+        });
+        let res = q.fetch_all(&self.connection).await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "List review queue query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(res.unwrap())
+    }
+
+    async fn mark_reviewed(&self, id: Id, reviewer_id: String) -> Result<(), ServiceError> {
+        let q = sqlx::query(
+            "UPDATE moderation_queue SET reviewed_at = NOW(), reviewed_by = uuid_or_null($2) WHERE _id = uuid_or_null($1) AND reviewed_at IS NULL;",
+        )
+        .bind(id.to_str())
+        .bind(reviewer_id);
+        let rows_affected = match q.execute(&self.connection).await {
+            Err(e) => {
+                event!(Level::ERROR, "Mark reviewed query failed: {}", e);
+                return Err(ServiceError::DbQueryError);
+            }
+            Ok(res) => res.rows_affected(),
+        };
+        if rows_affected == 0 {
+            return Err(ServiceError::ObjectNotFound);
+        }
+        Ok(())
+    }
+}
@@ -1,31 +1,224 @@
-use crate::types::question::{QuestByUser, QuestOut, QuestStatus};
+use crate::types::assignment::{AssignmentStrategy, StaffWorkloadOut};
+use crate::types::moderation::ReviewSubject;
+use crate::types::pagination::{Cursor, Keyset, KeysetPosition, Page};
+use crate::types::query::{QuestQuery, TagsMatch};
+use crate::types::question::{QuestByUser, QuestOut, QuestSearchHit, QuestStatus, StatusChangeOut};
+use crate::types::role::{Permission, Role};
 use crate::types::shared::Id;
 use error_handling::ServiceError;
+use std::future::Future;
 use std::str::FromStr;
 use tracing::{event, Level};
 
-use sqlx::postgres::PgRow;
-use sqlx::Row;
+use sqlx::postgres::{PgRow, Postgres};
+use sqlx::{QueryBuilder, Row, Transaction};
 
 use super::base::Db;
 
-impl Db {
-    pub async fn list_questions(&self, skip: i32, lim: Option<i32>) -> Result<Vec<QuestOut>, ServiceError> {
-        let q = sqlx::query(
-            "SELECT _id::text, created_at::text, title, content, tags, status::text, author::text FROM questions LIMIT $1 OFFSET $2;",
-        )
-        .bind(lim)
-        .bind(skip);
-        let q = q.map(|row: PgRow| QuestOut {
-            _id: row.get("_id"),
-            created_at: row.get("created_at"),
-            title: row.get("title"),
-            content: row.get("content"),
-            tags: row.get("tags"),
-            status: QuestStatus::from_str(row.get("status")).unwrap(),
-            author: row.get("author"),
+/// Delimiters `ts_headline` puts around the matches, control characters stripped from
+/// the text beforehand so they can't come from the question itself.
+pub(super) const MARK_START: char = '\u{2}';
+pub(super) const MARK_STOP: char = '\u{3}';
+
+/// Escapes the snippet for HTML and only then turns the match delimiters into `<mark>` tags.
+pub(super) fn mark_snippet(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+    for c in snippet.chars() {
+        match c {
+            MARK_START => html.push_str("<mark>"),
+            MARK_STOP => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+fn quest_from_row(row: &PgRow) -> QuestOut {
+    QuestOut {
+        _id: row.get("_id"),
+        created_at: row.get("created_at"),
+        title: row.get("title"),
+        content: row.get("content"),
+        tags: row.get("tags"),
+        status: QuestStatus::from_str(row.get("status")).unwrap(),
+        author: row.get("author"),
+        assignee: row.get("assignee"),
+    }
+}
+
+fn push_filters(builder: &mut QueryBuilder<Postgres>, query: &QuestQuery) {
+    if !query.status.is_empty() {
+        builder.push(" AND status = ANY(");
+        builder.push_bind(query.status.iter().map(|status| status.to_str()).collect::<Vec<String>>());
+        builder.push("::question_status[])");
+    }
+    if !query.tags.is_empty() {
+        builder.push(match query.tags_match.unwrap_or(TagsMatch::Any) {
+            TagsMatch::Any => " AND tags && ",
+            TagsMatch::All => " AND tags @> ",
         });
-        let res = q.fetch_all(&self.connection).await;
+        builder.push_bind(query.tags.clone());
+        builder.push("::text[]");
+    }
+    if let Some(author) = &query.author {
+        builder.push(" AND author = uuid_or_null(");
+        builder.push_bind(author.clone());
+        builder.push(")");
+    }
+    if let Some(after) = query.created_after {
+        builder.push(" AND created_at >= ");
+        builder.push_bind(after.to_string());
+        builder.push("::timestamp");
+    }
+    if let Some(before) = query.created_before {
+        builder.push(" AND created_at < ");
+        builder.push_bind(before.to_string());
+        builder.push("::timestamp");
+    }
+}
+
+fn push_pagination(builder: &mut QueryBuilder<Postgres>, query: &QuestQuery) {
+    builder.push(" LIMIT ");
+    builder.push_bind(query.pagination.limit);
+    builder.push(" OFFSET ");
+    builder.push_bind(query.pagination.offset);
+}
+
+/// Turns rows fetched in keyset order (with one extra row as a look-ahead) into a page with cursors.
+pub(super) fn keyset_page(mut items: Vec<QuestOut>, keyset: &Keyset) -> Page<QuestOut> {
+    let has_more = items.len() > keyset.limit as usize;
+    items.truncate(keyset.limit as usize);
+    if let KeysetPosition::Before(_) = keyset.position {
+        items.reverse();
+    }
+
+    let cursor_of = |quest: &QuestOut| {
+        Cursor {
+            created_at: quest.created_at.clone(),
+            id: quest._id.clone(),
+        }
+        .encode()
+    };
+    let (has_next, has_prev) = match keyset.position {
+        KeysetPosition::First => (has_more, false),
+        KeysetPosition::After(_) => (has_more, true),
+        KeysetPosition::Before(_) => (true, has_more),
+    };
+    Page {
+        next: items.last().filter(|_| has_next).map(cursor_of),
+        prev: items.first().filter(|_| has_prev).map(cursor_of),
+        items,
+        total: None,
+    }
+}
+
+/// Moves the question from `from` to `to`, failing if its status changed in the meantime, and records the change.
+async fn set_status(
+    tx: &mut Transaction<'_, Postgres>,
+    id: &Id,
+    from: QuestStatus,
+    to: QuestStatus,
+    actor_id: &str,
+) -> Result<(), ServiceError> {
+    let res = sqlx::query(
+        "UPDATE questions SET status = $1::question_status WHERE _id = uuid_or_null($2) AND status = $3::question_status;",
+    )
+    .bind(to.to_str())
+    .bind(id.to_str())
+    .bind(from.to_str())
+    .execute(&mut *tx)
+    .await;
+    let rows_affected = match res {
+        Err(e) => {
+            event!(Level::ERROR, "Change question status query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(res) => res.rows_affected(),
+    };
+    if rows_affected == 0 {
+        return Err(ServiceError::InvalidStatusTransition(format!(
+            "question is no longer {}",
+            from.to_str()
+        )));
+    }
+
+    let res = sqlx::query(
+        "INSERT INTO question_status_history (question, actor, old_status, new_status) VALUES (uuid_or_null($1), uuid_or_null($2), $3::question_status, $4::question_status);",
+    )
+    .bind(id.to_str())
+    .bind(actor_id)
+    .bind(from.to_str())
+    .bind(to.to_str())
+    .execute(&mut *tx)
+    .await;
+    if let Err(e) = res {
+        event!(Level::ERROR, "Record status change query failed: {}", e);
+        return Err(ServiceError::DbQueryError);
+    }
+    Ok(())
+}
+
+pub trait QuestionStore: std::fmt::Debug + Clone + Send + Sync + 'static {
+    fn list_questions(&self, query: &QuestQuery) -> impl Future<Output = Result<Vec<QuestOut>, ServiceError>> + Send;
+    fn list_questions_page(
+        &self,
+        query: &QuestQuery,
+        keyset: &Keyset,
+    ) -> impl Future<Output = Result<Page<QuestOut>, ServiceError>> + Send;
+    fn count_questions(&self, query: &QuestQuery) -> impl Future<Output = Result<i64, ServiceError>> + Send;
+    fn search_questions(&self, query: &QuestQuery) -> impl Future<Output = Result<Vec<QuestSearchHit>, ServiceError>> + Send;
+    fn add_question(&self, q: QuestByUser) -> impl Future<Output = Result<Id, ServiceError>> + Send;
+    /// Updates the question's texts and, if given, moves its status `(from, to)` in the same transaction.
+    fn update_question(
+        &self,
+        id: Id,
+        q: QuestByUser,
+        force: bool,
+        status_change: Option<(QuestStatus, QuestStatus)>,
+    ) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    fn delete_question(&self, id: Id, user_id: String, force: bool) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    fn get_question(&self, id: Id) -> impl Future<Output = Result<QuestOut, ServiceError>> + Send;
+    fn change_question_status(
+        &self,
+        id: Id,
+        from: QuestStatus,
+        to: QuestStatus,
+        actor_id: String,
+    ) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    fn list_status_history(&self, id: Id) -> impl Future<Output = Result<Vec<StatusChangeOut>, ServiceError>> + Send;
+    fn assign_question(
+        &self,
+        id: Id,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    fn list_assigned_questions(&self, assignee: String) -> impl Future<Output = Result<Vec<QuestOut>, ServiceError>> + Send;
+    fn staff_workload(&self) -> impl Future<Output = Result<Vec<StaffWorkloadOut>, ServiceError>> + Send;
+    fn pick_assignee(&self, strategy: AssignmentStrategy) -> impl Future<Output = Result<Option<String>, ServiceError>> + Send;
+    /// Puts a question or message accepted without a content check on the moderation queue.
+    fn queue_for_review(&self, subject: ReviewSubject) -> impl Future<Output = Result<(), ServiceError>> + Send;
+}
+
+impl QuestionStore for Db {
+    async fn list_questions(&self, query: &QuestQuery) -> Result<Vec<QuestOut>, ServiceError> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT _id::text, created_at::text, title, content, tags, status::text, author::text, assignee::text FROM questions WHERE TRUE",
+        );
+        push_filters(&mut builder, query);
+        builder.push(" ORDER BY ");
+        builder.push(query.sort.map(|sort| sort.as_sql()).unwrap_or("id ASC"));
+        push_pagination(&mut builder, query);
+
+        let res = builder
+            .build()
+            .map(|row: PgRow| quest_from_row(&row))
+            .fetch_all(&self.connection)
+            .await;
         if let Err(e) = res {
             event!(Level::ERROR, "List questions query failed: {}", e);
             return Err(ServiceError::DbQueryError);
@@ -33,7 +226,101 @@ impl Db {
         Ok(res.unwrap())
     }
 
-    pub async fn add_question(&self, q: QuestByUser) -> Result<Id, ServiceError> {
+    /// Keyset pagination over `(created_at, _id)`. One extra row is fetched
+    /// to find out whether there is a page beyond the requested one.
+    async fn list_questions_page(&self, query: &QuestQuery, keyset: &Keyset) -> Result<Page<QuestOut>, ServiceError> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT _id::text, created_at::text, title, content, tags, status::text, author::text, assignee::text FROM questions WHERE TRUE",
+        );
+        push_filters(&mut builder, query);
+        let (cursor, cmp, order) = match &keyset.position {
+            KeysetPosition::First => (None, "", "ASC"),
+            KeysetPosition::After(cursor) => (Some(cursor), ">", "ASC"),
+            KeysetPosition::Before(cursor) => (Some(cursor), "<", "DESC"),
+        };
+        if let Some(cursor) = cursor {
+            builder.push(format!(" AND (created_at, _id) {} (", cmp));
+            builder.push_bind(cursor.created_at.clone());
+            builder.push("::timestamp, uuid_or_null(");
+            builder.push_bind(cursor.id.clone());
+            builder.push("))");
+        }
+        builder.push(format!(" ORDER BY created_at {}, _id {} LIMIT ", order, order));
+        builder.push_bind(keyset.limit + 1);
+
+        let res = builder
+            .build()
+            .map(|row: PgRow| quest_from_row(&row))
+            .fetch_all(&self.connection)
+            .await;
+        let items = match res {
+            Err(e) => {
+                event!(Level::ERROR, "List questions page query failed: {}", e);
+                return Err(ServiceError::DbQueryError);
+            }
+            Ok(items) => items,
+        };
+        Ok(keyset_page(items, keyset))
+    }
+
+    async fn count_questions(&self, query: &QuestQuery) -> Result<i64, ServiceError> {
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT COUNT(*) AS total FROM questions WHERE TRUE");
+        if let Some(search) = &query.search {
+            builder.push(" AND search_vector @@ websearch_to_tsquery('english', ");
+            builder.push_bind(search.clone());
+            builder.push(")");
+        }
+        push_filters(&mut builder, query);
+
+        let res = builder
+            .build()
+            .map(|row: PgRow| row.get::<i64, _>("total"))
+            .fetch_one(&self.connection)
+            .await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "Count questions query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(res.unwrap())
+    }
+
+    /// Full-text search over question titles and contents, best matches first unless
+    /// another order is requested. Snippets are HTML escaped, with the matched terms wrapped in `<mark>` tags.
+    async fn search_questions(&self, query: &QuestQuery) -> Result<Vec<QuestSearchHit>, ServiceError> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT _id::text, created_at::text, title, content, tags, status::text, author::text, assignee::text, \
+                ts_rank(search_vector, search_query) AS rank, \
+                ts_headline('english', translate(title, chr(2) || chr(3), ''), search_query, \
+                    'StartSel=' || chr(2) || ', StopSel=' || chr(3) || ', HighlightAll=true') AS title_snippet, \
+                ts_headline('english', translate(content, chr(2) || chr(3), ''), search_query, \
+                    'StartSel=' || chr(2) || ', StopSel=' || chr(3) || ', MaxFragments=2') AS content_snippet \
+            FROM questions, websearch_to_tsquery('english', ",
+        );
+        builder.push_bind(query.search.clone().unwrap_or_default());
+        builder.push(") search_query WHERE search_vector @@ search_query");
+        push_filters(&mut builder, query);
+        builder.push(" ORDER BY ");
+        builder.push(query.sort.map(|sort| sort.as_sql()).unwrap_or("rank DESC, created_at DESC"));
+        push_pagination(&mut builder, query);
+
+        let res = builder
+            .build()
+            .map(|row: PgRow| QuestSearchHit {
+                question: quest_from_row(&row),
+                rank: row.get("rank"),
+                title_snippet: mark_snippet(row.get("title_snippet")),
+                content_snippet: mark_snippet(row.get("content_snippet")),
+            })
+            .fetch_all(&self.connection)
+            .await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "Search questions query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(res.unwrap())
+    }
+
+    async fn add_question(&self, q: QuestByUser) -> Result<Id, ServiceError> {
         let quest_status = q.parse_status();
         let res = sqlx::query(
             "INSERT INTO questions (title, content, tags, status, author) VALUES ($1, $2, $3, $4::question_status, uuid_or_null($5)) RETURNING _id::text;",
@@ -53,20 +340,31 @@ impl Db {
         Ok(res.unwrap())
     }
 
-    pub async fn update_question(&self, id: Id, q: QuestByUser, force: bool) -> Result<(), ServiceError> {
-        let quest_status = q.parse_status();
+    async fn update_question(
+        &self,
+        id: Id,
+        q: QuestByUser,
+        force: bool,
+        status_change: Option<(QuestStatus, QuestStatus)>,
+    ) -> Result<(), ServiceError> {
+        let mut tx = self.connection.begin().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to start transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+
         let stmt = match force {
-            true => "UPDATE questions SET title = $1, content = $2, tags = $3, status = $4::question_status WHERE _id = uuid_or_null($5);",
-            false => "UPDATE questions SET title = $1, content = $2, tags = $3, status = $4::question_status WHERE _id = uuid_or_null($5) AND author = uuid_or_null($6);"
+            true => "UPDATE questions SET title = $1, content = $2, tags = $3 WHERE _id = uuid_or_null($4);",
+            false => "UPDATE questions SET title = $1, content = $2, tags = $3 WHERE _id = uuid_or_null($4) AND author = uuid_or_null($5);"
         };
-        let q = sqlx::query(stmt)
+        let res = sqlx::query(stmt)
             .bind(q.title)
             .bind(q.content)
             .bind(q.tags)
-            .bind(quest_status)
             .bind(id.to_str())
-            .bind(q.user_id);
-        let rows_affected = match q.execute(&self.connection).await {
+            .bind(&q.user_id)
+            .execute(&mut tx)
+            .await;
+        let rows_affected = match res {
             Err(e) => {
                 event!(Level::ERROR, "Update question query failed: {}", e);
                 return Err(ServiceError::DbQueryError);
@@ -76,10 +374,17 @@ impl Db {
         if rows_affected == 0 {
             return Err(ServiceError::ObjectNotFound);
         }
-        Ok(())
+        if let Some((from, to)) = status_change {
+            set_status(&mut tx, &id, from, to, &q.user_id).await?;
+        }
+
+        tx.commit().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to commit transaction: {}", e);
+            ServiceError::DbQueryError
+        })
     }
 
-    pub async fn delete_question(&self, id: Id, user_id: String, force: bool) -> Result<(), ServiceError> {
+    async fn delete_question(&self, id: Id, user_id: String, force: bool) -> Result<(), ServiceError> {
         let stmt = match force {
             true => "DELETE FROM questions WHERE _id = uuid_or_null($1);",
             false => "DELETE FROM questions WHERE _id = uuid_or_null($1) and author = uuid_or_null($2);",
@@ -98,24 +403,178 @@ impl Db {
         Ok(())
     }
 
-    pub async fn get_question(&self, id: Id) -> Result<QuestOut, ServiceError> {
+    async fn get_question(&self, id: Id) -> Result<QuestOut, ServiceError> {
         let q = sqlx::query(
-            "SELECT _id::text, created_at::text, title, content, tags, status::text, author::text FROM questions WHERE _id = uuid_or_null($1);",
+            "SELECT _id::text, created_at::text, title, content, tags, status::text, author::text, assignee::text FROM questions WHERE _id = uuid_or_null($1);",
         )
         .bind(id.to_str());
-        let q = q.map(|row: PgRow| QuestOut {
-            _id: row.get("_id"),
-            created_at: row.get("created_at"),
-            title: row.get("title"),
-            content: row.get("content"),
-            tags: row.get("tags"),
-            status: QuestStatus::from_str(row.get("status")).unwrap(),
-            author: row.get("author"),
-        });
+        let q = q.map(|row: PgRow| quest_from_row(&row));
         let res = q.fetch_one(&self.connection).await;
         if res.is_err() {
             return Err(ServiceError::ObjectNotFound);
         }
         Ok(res.unwrap())
     }
+
+    /// Moves the question to a new status and records the change in its history.
+    /// The update only applies if the question is still in the `from` status,
+    /// so concurrent transitions can't silently overwrite each other.
+    async fn change_question_status(
+        &self,
+        id: Id,
+        from: QuestStatus,
+        to: QuestStatus,
+        actor_id: String,
+    ) -> Result<(), ServiceError> {
+        let mut tx = self.connection.begin().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to start transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+        set_status(&mut tx, &id, from, to, &actor_id).await?;
+        tx.commit().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to commit transaction: {}", e);
+            ServiceError::DbQueryError
+        })
+    }
+
+    async fn list_status_history(&self, id: Id) -> Result<Vec<StatusChangeOut>, ServiceError> {
+        let q = sqlx::query(
+            "SELECT _id::text, created_at::text, actor::text, old_status::text, new_status::text FROM question_status_history WHERE question = uuid_or_null($1) ORDER BY created_at, id;",
+        )
+        .bind(id.to_str())
+        .map(|row: PgRow| StatusChangeOut {
+            _id: row.get("_id"),
+            created_at: row.get("created_at"),
+            actor: row.get("actor"),
+            old_status: QuestStatus::from_str(row.get("old_status")).unwrap(),
+            new_status: QuestStatus::from_str(row.get("new_status")).unwrap(),
+        });
+        let res = q.fetch_all(&self.connection).await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "List status history query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(res.unwrap())
+    }
+
+    /// Sets the question's assignee (or clears it with `None`), provided it is still assigned to `from`.
+    async fn assign_question(&self, id: Id, from: Option<String>, to: Option<String>) -> Result<(), ServiceError> {
+        let res = sqlx::query(
+            "UPDATE questions SET assignee = uuid_or_null($1), assigned_at = CASE WHEN $1 IS NULL THEN NULL ELSE NOW() END \
+            WHERE _id = uuid_or_null($2) AND assignee IS NOT DISTINCT FROM uuid_or_null($3);",
+        )
+        .bind(to)
+        .bind(id.to_str())
+        .bind(from)
+        .execute(&self.connection)
+        .await;
+        let rows_affected = match res {
+            Err(e) => {
+                event!(Level::ERROR, "Assign question query failed: {}", e);
+                return Err(ServiceError::DbQueryError);
+            }
+            Ok(res) => res.rows_affected(),
+        };
+        if rows_affected == 0 {
+            return Err(ServiceError::ConcurrentModification);
+        }
+        Ok(())
+    }
+
+    async fn list_assigned_questions(&self, assignee: String) -> Result<Vec<QuestOut>, ServiceError> {
+        let q = sqlx::query(
+            "SELECT _id::text, created_at::text, title, content, tags, status::text, author::text, assignee::text FROM questions \
+            WHERE assignee = uuid_or_null($1) AND status IN ('Pending', 'Unresolved') ORDER BY created_at, id;",
+        )
+        .bind(assignee)
+        .map(|row: PgRow| quest_from_row(&row));
+        let res = q.fetch_all(&self.connection).await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "List assigned questions query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(res.unwrap())
+    }
+
+    async fn staff_workload(&self) -> Result<Vec<StaffWorkloadOut>, ServiceError> {
+        let q = sqlx::query(
+            "SELECT u._id::text, u.email, u.first_name, u.last_name, \
+                COUNT(q._id) FILTER (WHERE q.status IN ('Pending', 'Unresolved')) AS open_questions, \
+                COUNT(q._id) AS assigned_questions \
+            FROM users u LEFT JOIN questions q ON q.assignee = u._id \
+            WHERE u.role::text = ANY($1) AND u.deactivated_at IS NULL GROUP BY u._id, u.id ORDER BY open_questions DESC, u.id;",
+        )
+        .bind(Role::granting(Permission::HandleQuestions))
+        .map(|row: PgRow| StaffWorkloadOut {
+            _id: row.get("_id"),
+            email: row.get("email"),
+            first_name: row.get("first_name"),
+            last_name: row.get("last_name"),
+            open_questions: row.get("open_questions"),
+            assigned_questions: row.get("assigned_questions"),
+        });
+        let res = q.fetch_all(&self.connection).await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "Staff workload query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(res.unwrap())
+    }
+
+    async fn pick_assignee(&self, strategy: AssignmentStrategy) -> Result<Option<String>, ServiceError> {
+        let order = match strategy {
+            AssignmentStrategy::Off => return Ok(None),
+            AssignmentStrategy::RoundRobin => "MAX(q.assigned_at) ASC NULLS FIRST, u.id",
+            AssignmentStrategy::LeastLoaded => {
+                "COUNT(q._id) FILTER (WHERE q.status IN ('Pending', 'Unresolved')) ASC, MAX(q.assigned_at) ASC NULLS FIRST, u.id"
+            }
+        };
+        let stmt = format!(
+            "SELECT u._id::text FROM users u LEFT JOIN questions q ON q.assignee = u._id \
+            WHERE u.role::text = ANY($1) AND u.deactivated_at IS NULL GROUP BY u._id, u.id ORDER BY {} LIMIT 1;",
+            order
+        );
+        let res = sqlx::query(&stmt)
+            .bind(Role::granting(Permission::HandleQuestions))
+            .map(|row: PgRow| row.get::<String, _>("_id"))
+            .fetch_optional(&self.connection)
+            .await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "Pick assignee query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(res.unwrap())
+    }
+
+    async fn queue_for_review(&self, subject: ReviewSubject) -> Result<(), ServiceError> {
+        let (question, message) = match subject {
+            ReviewSubject::Question(id) => (Some(id.to_str()), None),
+            ReviewSubject::Message(id) => (None, Some(id.to_str())),
+        };
+        let res = sqlx::query("INSERT INTO moderation_queue (question, message) VALUES (uuid_or_null($1), uuid_or_null($2));")
+            .bind(question)
+            .bind(message)
+            .execute(&self.connection)
+            .await;
+
+        if let Err(e) = res {
+            event!(Level::ERROR, "Queue for review query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mark_snippet_escapes_the_text_around_the_matches() {
+        let snippet = format!("<script>alert(\"{}x{}\")</script> & 'y'", MARK_START, MARK_STOP);
+        assert_eq!(
+            mark_snippet(&snippet),
+            "&lt;script&gt;alert(&quot;<mark>x</mark>&quot;)&lt;/script&gt; &amp; &#39;y&#39;"
+        );
+    }
 }
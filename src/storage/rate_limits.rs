@@ -0,0 +1,70 @@
+use crate::ratelimit::{Quota, RateLimitStore};
+use error_handling::ServiceError;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::atomic::{AtomicU32, Ordering};
+use tracing::{event, Level};
+
+use super::base::Db;
+
+/// Every this many acquisitions, the full buckets get dropped, they hold nothing a new bucket wouldn't.
+const PRUNE_EVERY: u32 = 100;
+
+static ACQUISITIONS: AtomicU32 = AtomicU32::new(0);
+
+impl RateLimitStore for Db {
+    /// The bucket row stays locked until the transaction ends, so instances sharing it take turns.
+    async fn acquire(&self, key: String, quota: Quota) -> Result<(), ServiceError> {
+        if ACQUISITIONS.fetch_add(1, Ordering::Relaxed).is_multiple_of(PRUNE_EVERY) {
+            let res = sqlx::query("DELETE FROM rate_limit_buckets WHERE full_at < NOW();")
+                .execute(&self.connection)
+                .await;
+            if let Err(e) = res {
+                event!(Level::WARN, "Failed to prune full rate limit buckets: {}", e);
+            }
+        }
+
+        let mut tx = self.connection.begin().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to start transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+
+        // upserting locks the row even when it already exists, or was just pruned
+        let res = sqlx::query(
+            "INSERT INTO rate_limit_buckets (key, tokens) VALUES ($1, $2) ON CONFLICT (key) DO UPDATE SET key = EXCLUDED.key \
+            RETURNING tokens, EXTRACT(EPOCH FROM NOW() - updated_at)::float8 AS elapsed;",
+        )
+        .bind(&key)
+        .bind(quota.capacity as f64)
+        .map(|row: PgRow| (row.get::<f64, _>("tokens"), row.get::<f64, _>("elapsed")))
+        .fetch_one(&mut tx)
+        .await;
+        let tokens = match res {
+            Ok((tokens, elapsed)) => quota.refill(tokens, elapsed),
+            Err(e) => {
+                event!(Level::ERROR, "Get rate limit bucket query failed: {}", e);
+                return Err(ServiceError::DbQueryError);
+            }
+        };
+
+        let (left, acquired) = quota.take(tokens);
+        let res = sqlx::query(
+            "UPDATE rate_limit_buckets SET tokens = $2, updated_at = NOW(), full_at = NOW() + make_interval(secs => $3) WHERE key = $1;",
+        )
+        .bind(&key)
+        .bind(left)
+        .bind(quota.secs_until_full(left))
+        .execute(&mut tx)
+        .await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "Update rate limit bucket query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+
+        tx.commit().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to commit transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+        acquired
+    }
+}
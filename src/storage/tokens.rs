@@ -0,0 +1,255 @@
+use crate::types::{auth::Claims, role::Role, user::UserTknDetails};
+use error_handling::ServiceError;
+use sqlx::postgres::{PgRow, Postgres};
+use sqlx::{Row, Transaction};
+use std::future::Future;
+use std::str::FromStr;
+use tracing::{event, Level};
+use uuid::Uuid;
+
+use super::base::Db;
+
+pub(super) const REFRESH_TOKEN_EXP_DAYS: i32 = 30;
+
+pub(super) fn generate_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Invalidates the user's access tokens issued so far and revokes all of their refresh tokens.
+pub(super) async fn revoke_sessions(tx: &mut Transaction<'_, Postgres>, user_id: &str) -> Result<(), ServiceError> {
+    let rows_affected = match sqlx::query("UPDATE users SET sessions_revoked_at = NOW() WHERE _id = uuid_or_null($1);")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+    {
+        Err(e) => {
+            event!(Level::ERROR, "Revoke user sessions query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(res) => res.rows_affected(),
+    };
+    if rows_affected == 0 {
+        return Err(ServiceError::ObjectNotFound);
+    }
+
+    let res =
+        sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = uuid_or_null($1) AND revoked_at IS NULL;")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await;
+    if let Err(e) = res {
+        event!(Level::ERROR, "Revoke user refresh tokens query failed: {}", e);
+        return Err(ServiceError::DbQueryError);
+    }
+    Ok(())
+}
+
+/// Refresh tokens and the revocation of access tokens.
+pub trait TokenStore: std::fmt::Debug + Clone + Send + Sync + 'static {
+    /// Persists a new refresh token for the user and returns its plain text value.
+    /// Only the token's digest is stored. Tokens issued on login start a new family,
+    /// while tokens issued on rotation inherit the family of the token they replace.
+    fn issue_refresh_token(
+        &self,
+        user_id: String,
+        family: Option<String>,
+    ) -> impl Future<Output = Result<String, ServiceError>> + Send;
+    /// Exchanges a valid refresh token for a new one from the same family.
+    /// Presenting a token that has already been rotated or revoked is treated as
+    /// a replay and revokes every token in its family.
+    fn rotate_refresh_token(&self, token: String) -> impl Future<Output = Result<(UserTknDetails, String), ServiceError>> + Send;
+    /// Revokes the family of the user's refresh token, tokens of other users are left alone.
+    fn revoke_refresh_token_family(
+        &self,
+        token: String,
+        user_id: String,
+    ) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    /// Puts the access token's `jti` on the deny list until the token expires.
+    fn revoke_token(&self, claims: &Claims) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    /// Invalidates every access token issued to the user so far and revokes all of their refresh tokens.
+    fn revoke_user_sessions(&self, user_id: String) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    /// Tells whether the access token was revoked, on its own or with all of its user's sessions.
+    fn is_token_revoked(&self, claims: &Claims) -> impl Future<Output = Result<bool, ServiceError>> + Send;
+}
+
+impl TokenStore for Db {
+    async fn issue_refresh_token(&self, user_id: String, family: Option<String>) -> Result<String, ServiceError> {
+        let token = generate_token();
+        let res = sqlx::query(
+            "INSERT INTO refresh_tokens (token_hash, family, user_id, expires_at) VALUES (encode(digest($1, 'sha256'), 'hex'), COALESCE(uuid_or_null($2), gen_random_uuid()), uuid_or_null($3), NOW() + make_interval(days => $4));",
+        )
+        .bind(&token)
+        .bind(family)
+        .bind(user_id)
+        .bind(REFRESH_TOKEN_EXP_DAYS)
+        .execute(&self.connection)
+        .await;
+
+        if let Err(e) = res {
+            event!(Level::ERROR, "Issue refresh token query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(token)
+    }
+
+    async fn rotate_refresh_token(&self, token: String) -> Result<(UserTknDetails, String), ServiceError> {
+        let mut tx = self.connection.begin().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to start transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+
+        let res = sqlx::query(
+            "SELECT t.family::text, t.rotated_at IS NOT NULL OR t.revoked_at IS NOT NULL AS spent, t.expires_at < NOW() AS expired, u._id::text AS user_id, u.role::text FROM refresh_tokens t JOIN users u ON u._id = t.user_id WHERE t.token_hash = encode(digest($1, 'sha256'), 'hex') FOR UPDATE OF t;",
+        )
+        .bind(&token)
+        .map(|row: PgRow| {
+            (
+                row.get::<String, _>("family"),
+                row.get::<bool, _>("spent"),
+                row.get::<bool, _>("expired"),
+                UserTknDetails {
+                    _id: row.get("user_id"),
+                    role: Role::from_str(row.get("role")).unwrap(),
+                },
+            )
+        })
+        .fetch_optional(&mut tx)
+        .await;
+
+        let (family, spent, expired, user) = match res {
+            Err(e) => {
+                event!(Level::ERROR, "Get refresh token query failed: {}", e);
+                return Err(ServiceError::DbQueryError);
+            }
+            Ok(None) => return Err(ServiceError::AuthTokenMissingOrInvalid),
+            Ok(Some(found)) => found,
+        };
+
+        if spent {
+            event!(Level::WARN, "Refresh token reuse detected, revoking token family {}", family);
+            let res = sqlx::query(
+                "UPDATE refresh_tokens SET revoked_at = NOW() WHERE family = uuid_or_null($1) AND revoked_at IS NULL;",
+            )
+            .bind(&family)
+            .execute(&mut tx)
+            .await;
+            if let Err(e) = res {
+                event!(Level::ERROR, "Revoke refresh token family query failed: {}", e);
+                return Err(ServiceError::DbQueryError);
+            }
+            tx.commit().await.map_err(|e| {
+                event!(Level::ERROR, "Failed to commit transaction: {}", e);
+                ServiceError::DbQueryError
+            })?;
+            return Err(ServiceError::AuthTokenMissingOrInvalid);
+        }
+
+        if expired {
+            return Err(ServiceError::AuthTokenMissingOrInvalid);
+        }
+
+        let new_token = generate_token();
+        let res =
+            sqlx::query("UPDATE refresh_tokens SET rotated_at = NOW() WHERE token_hash = encode(digest($1, 'sha256'), 'hex');")
+                .bind(&token)
+                .execute(&mut tx)
+                .await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "Rotate refresh token query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        let res = sqlx::query(
+            "INSERT INTO refresh_tokens (token_hash, family, user_id, expires_at) VALUES (encode(digest($1, 'sha256'), 'hex'), uuid_or_null($2), uuid_or_null($3), NOW() + make_interval(days => $4));",
+        )
+        .bind(&new_token)
+        .bind(&family)
+        .bind(&user._id)
+        .bind(REFRESH_TOKEN_EXP_DAYS)
+        .execute(&mut tx)
+        .await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "Issue refresh token query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+
+        tx.commit().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to commit transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+        Ok((user, new_token))
+    }
+
+    async fn revoke_refresh_token_family(&self, token: String, user_id: String) -> Result<(), ServiceError> {
+        let res = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE family = (SELECT family FROM refresh_tokens WHERE token_hash = encode(digest($1, 'sha256'), 'hex') AND user_id = uuid_or_null($2)) AND revoked_at IS NULL;",
+        )
+        .bind(token)
+        .bind(user_id)
+        .execute(&self.connection)
+        .await;
+
+        if let Err(e) = res {
+            event!(Level::ERROR, "Revoke refresh token family query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(())
+    }
+
+    async fn revoke_token(&self, claims: &Claims) -> Result<(), ServiceError> {
+        let res = sqlx::query("DELETE FROM revoked_tokens WHERE expires_at < NOW();")
+            .execute(&self.connection)
+            .await;
+        if let Err(e) = res {
+            event!(Level::WARN, "Failed to purge expired revoked tokens: {}", e);
+        }
+
+        let res = sqlx::query(
+            "INSERT INTO revoked_tokens (jti, user_id, expires_at) VALUES (uuid_or_null($1), uuid_or_null($2), to_timestamp($3)::timestamp) ON CONFLICT (jti) DO NOTHING;",
+        )
+        .bind(&claims.jti)
+        .bind(&claims.sub)
+        .bind(claims.exp as f64)
+        .execute(&self.connection)
+        .await;
+
+        if let Err(e) = res {
+            event!(Level::ERROR, "Revoke token query failed: {}", e);
+            return Err(ServiceError::DbQueryError);
+        }
+        Ok(())
+    }
+
+    async fn revoke_user_sessions(&self, user_id: String) -> Result<(), ServiceError> {
+        let mut tx = self.connection.begin().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to start transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+
+        revoke_sessions(&mut tx, &user_id).await?;
+
+        tx.commit().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to commit transaction: {}", e);
+            ServiceError::DbQueryError
+        })
+    }
+
+    async fn is_token_revoked(&self, claims: &Claims) -> Result<bool, ServiceError> {
+        let res = sqlx::query(
+            "SELECT EXISTS (SELECT 1 FROM revoked_tokens WHERE jti = uuid_or_null($1)) OR EXISTS (SELECT 1 FROM users WHERE _id = uuid_or_null($2) AND (sessions_revoked_at > to_timestamp($3)::timestamp OR deactivated_at IS NOT NULL)) AS revoked;",
+        )
+        .bind(&claims.jti)
+        .bind(&claims.sub)
+        .bind(claims.iat)
+        .map(|row: PgRow| row.get::<bool, _>("revoked"))
+        .fetch_one(&self.connection)
+        .await;
+
+        match res {
+            Ok(revoked) => Ok(revoked),
+            Err(e) => {
+                event!(Level::ERROR, "Check token revocation query failed: {}", e);
+                Err(ServiceError::DbQueryError)
+            }
+        }
+    }
+}
@@ -1,30 +1,76 @@
 use crate::types::{
-    auth::Creds,
+    auth::{Creds, LoginLockout},
+    pagination::Pagination,
+    role::Role,
     shared::Id,
-    user::{UserIn, UserOut},
+    user::{UserIn, UserOut, UserPatch},
 };
 use error_handling::ServiceError;
 use sqlx::postgres::PgRow;
 use sqlx::Row;
+use std::future::Future;
 use std::str::FromStr;
 use tracing::{event, instrument, Level};
 
-async fn get_db_err_code(e: &sqlx::Error) -> u16 {
+pub(super) async fn get_db_err_code(e: &sqlx::Error) -> u16 {
     if let Some(db_err) = e.as_database_error() {
         return db_err.code().unwrap().parse::<u16>().unwrap();
     }
     0
 }
 
-impl super::base::Db {
-    #[instrument]
-    pub async fn add_user(&self, u: UserIn) -> Result<Id, ServiceError> {
-        let res = sqlx::query("INSERT INTO users (email, password, first_name, last_name, is_moderator) VALUES($1, crypt($2, gen_salt('bf', 8)), $3, $4, $5) RETURNING _id::text;")
+fn user_from_row(row: &PgRow) -> UserOut {
+    UserOut {
+        _id: row.get("_id"),
+        created_at: row.get("created_at"),
+        email: row.get("email"),
+        first_name: row.get("first_name"),
+        last_name: row.get("last_name"),
+        role: Role::from_str(row.get("role")).unwrap(),
+        deactivated_at: row.get("deactivated_at"),
+        email_verified_at: row.get("email_verified_at"),
+    }
+}
+
+pub trait UserStore: std::fmt::Debug + Clone + Send + Sync + 'static {
+    fn add_user(&self, u: UserIn) -> impl Future<Output = Result<Id, ServiceError>> + Send;
+    /// Counts failed attempts and refuses accounts locked by `lockout`, even with the right password.
+    fn get_user_by_creds(
+        &self,
+        creds: Creds,
+        lockout: LoginLockout,
+    ) -> impl Future<Output = Result<UserOut, ServiceError>> + Send;
+    fn get_user(&self, id: Id) -> impl Future<Output = Result<UserOut, ServiceError>> + Send;
+    fn set_user_role(&self, id: Id, role: Role) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    /// Makes the active user with `email` an admin unless there is an admin already, telling whether it did.
+    fn bootstrap_admin(&self, email: String) -> impl Future<Output = Result<bool, ServiceError>> + Send;
+    fn list_users(&self, pagination: &Pagination) -> impl Future<Output = Result<Vec<UserOut>, ServiceError>> + Send;
+    /// A changed email counts as unverified again.
+    fn update_user(&self, id: Id, patch: UserPatch) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    fn set_user_active(&self, id: Id, active: bool) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    fn delete_user(&self, id: Id) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    fn get_active_user_by_email(&self, email: String) -> impl Future<Output = Result<UserOut, ServiceError>> + Send;
+    /// Tells whether any account, deactivated ones included, uses the email.
+    fn is_email_taken(&self, email: String) -> impl Future<Output = Result<bool, ServiceError>> + Send;
+    fn mark_email_verified(&self, id: Id) -> impl Future<Output = Result<(), ServiceError>> + Send;
+    fn change_password(
+        &self,
+        id: Id,
+        current_password: String,
+        new_password: String,
+    ) -> impl Future<Output = Result<(), ServiceError>> + Send;
+}
+
+impl UserStore for super::base::Db {
+    #[instrument(skip_all)]
+    async fn add_user(&self, u: UserIn) -> Result<Id, ServiceError> {
+        let role = u.role();
+        let res = sqlx::query("INSERT INTO users (email, password, first_name, last_name, role) VALUES($1, crypt($2, gen_salt('bf', 8)), $3, $4, $5::user_role) RETURNING _id::text;")
             .bind(u.email)
             .bind(u.password)
             .bind(u.first_name)
             .bind(u.last_name)
-            .bind(u.is_moderator.unwrap_or(false))
+            .bind(role.to_str())
             .map(|row: PgRow| Id::from_str(row.get("_id")).unwrap())
             .fetch_one(&self.connection).await;
 
@@ -40,31 +86,253 @@ impl super::base::Db {
         Ok(res.unwrap())
     }
 
-    pub async fn get_user_by_creds(&self, creds: Creds) -> Result<UserOut, ServiceError> {
-        let res = sqlx::query("SELECT _id::text, created_at::text, email, first_name, last_name, is_moderator, is_staff, is_superuser FROM users WHERE email = $1 AND password = crypt($2, password);")
+    async fn get_user_by_creds(&self, creds: Creds, lockout: LoginLockout) -> Result<UserOut, ServiceError> {
+        // The row stays locked until commit, so concurrent attempts can't slip past the count or the lockout.
+        let mut tx = self.connection.begin().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to start transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+        let res = sqlx::query("SELECT _id::text, created_at::text, email, first_name, last_name, role::text, deactivated_at::text, email_verified_at::text, password = crypt($2, password) AS password_ok, CEIL(EXTRACT(EPOCH FROM locked_until - NOW()))::bigint AS locked_secs, failed_logins FROM users WHERE email = $1 AND deactivated_at IS NULL FOR UPDATE;")
             .bind(creds.email)
             .bind(creds.password)
             .map(|row: PgRow| {
-                UserOut {
-                    _id: row.get("_id"),
-                    created_at: row.get("created_at"),
-                    email: row.get("email"),
-                    first_name: row.get("first_name"),
-                    last_name: row.get("last_name"),
-                    is_moderator: row.get("is_moderator"),
-                    is_staff: row.get("is_staff"),
-                    is_superuser: row.get("is_superuser")
-                }
-            }).fetch_one(&self.connection).await;
-        if let Err(e) = res {
-            let no_rows_returned = get_db_err_code(&e).await == 0;
-            if no_rows_returned {
-                event!(Level::WARN, "{}", e);
-                return Err(ServiceError::ObjectNotFound);
+                let locked_secs: Option<i64> = row.get("locked_secs");
+                (user_from_row(&row), row.get::<bool, _>("password_ok"), locked_secs.filter(|secs| *secs > 0), row.get::<i32, _>("failed_logins"))
+            })
+            .fetch_optional(&mut tx)
+            .await;
+        let (user, password_ok, locked_secs, failed_logins) = match res {
+            Ok(Some(found)) => found,
+            Ok(None) => return Err(ServiceError::ObjectNotFound),
+            Err(e) => {
+                event!(Level::ERROR, "{}", e);
+                return Err(ServiceError::DbQueryError);
             }
-            event!(Level::ERROR, "{}", e);
+        };
+        if let Some(secs) = locked_secs {
+            return Err(ServiceError::AccountLocked(secs as u64));
+        }
+
+        let (failures, lock_secs) = match password_ok {
+            true => (0, None),
+            false => (failed_logins + 1, lockout.lock_secs(failed_logins + 1)),
+        };
+        if failures != failed_logins || lock_secs.is_some() {
+            let res = sqlx::query("UPDATE users SET failed_logins = $2, locked_until = NOW() + make_interval(secs => $3) WHERE _id = uuid_or_null($1);")
+                .bind(&user._id)
+                .bind(failures)
+                .bind(lock_secs.map(|secs| secs as f64))
+                .execute(&mut tx)
+                .await;
+            if let Err(e) = res {
+                event!(Level::ERROR, "Count failed logins query failed: {}", e);
+                return Err(ServiceError::DbQueryError);
+            }
+        }
+        tx.commit().await.map_err(|e| {
+            event!(Level::ERROR, "Failed to commit transaction: {}", e);
+            ServiceError::DbQueryError
+        })?;
+
+        if let Some(secs) = lock_secs {
+            event!(
+                Level::WARN,
+                "Locking user {} for {} seconds after {} failed logins",
+                user._id,
+                secs,
+                failures
+            );
+        }
+        match password_ok {
+            true => Ok(user),
+            false => Err(ServiceError::ObjectNotFound),
+        }
+    }
+
+    async fn get_user(&self, id: Id) -> Result<UserOut, ServiceError> {
+        let res = sqlx::query("SELECT _id::text, created_at::text, email, first_name, last_name, role::text, deactivated_at::text, email_verified_at::text FROM users WHERE _id = uuid_or_null($1);")
+            .bind(id.to_str())
+            .map(|row: PgRow| user_from_row(&row))
+            .fetch_optional(&self.connection)
+            .await;
+        match res {
+            Ok(Some(user)) => Ok(user),
+            Ok(None) => Err(ServiceError::ObjectNotFound),
+            Err(e) => {
+                event!(Level::ERROR, "Get user query failed: {}", e);
+                Err(ServiceError::DbQueryError)
+            }
+        }
+    }
+
+    async fn set_user_role(&self, id: Id, role: Role) -> Result<(), ServiceError> {
+        let res = sqlx::query("UPDATE users SET role = $1::user_role WHERE _id = uuid_or_null($2);")
+            .bind(role.to_str())
+            .bind(id.to_str())
+            .execute(&self.connection)
+            .await;
+        match res {
+            Ok(res) if res.rows_affected() == 0 => Err(ServiceError::ObjectNotFound),
+            Ok(_) => Ok(()),
+            Err(e) => {
+                event!(Level::ERROR, "Set user role query failed: {}", e);
+                Err(ServiceError::DbQueryError)
+            }
+        }
+    }
+
+    async fn bootstrap_admin(&self, email: String) -> Result<bool, ServiceError> {
+        let res = sqlx::query(
+            "UPDATE users SET role = 'admin'::user_role WHERE email = $1 AND deactivated_at IS NULL \
+            AND NOT EXISTS (SELECT 1 FROM users WHERE role = 'admin'::user_role);",
+        )
+        .bind(email)
+        .execute(&self.connection)
+        .await;
+        match res {
+            Ok(res) => Ok(res.rows_affected() > 0),
+            Err(e) => {
+                event!(Level::ERROR, "Bootstrap admin query failed: {}", e);
+                Err(ServiceError::DbQueryError)
+            }
+        }
+    }
+
+    async fn list_users(&self, pagination: &Pagination) -> Result<Vec<UserOut>, ServiceError> {
+        let res = sqlx::query("SELECT _id::text, created_at::text, email, first_name, last_name, role::text, deactivated_at::text, email_verified_at::text FROM users ORDER BY id OFFSET $1 LIMIT $2;")
+            .bind(pagination.offset)
+            .bind(pagination.limit)
+            .map(|row: PgRow| user_from_row(&row))
+            .fetch_all(&self.connection)
+            .await;
+        if let Err(e) = res {
+            event!(Level::ERROR, "List users query failed: {}", e);
             return Err(ServiceError::DbQueryError);
         }
         Ok(res.unwrap())
     }
+
+    async fn update_user(&self, id: Id, patch: UserPatch) -> Result<(), ServiceError> {
+        let res = sqlx::query("UPDATE users SET email = COALESCE($1, email), email_verified_at = CASE WHEN $1 <> email THEN NULL ELSE email_verified_at END, \
+            first_name = COALESCE($2, first_name), last_name = COALESCE($3, last_name) WHERE _id = uuid_or_null($4);")
+            .bind(patch.email)
+            .bind(patch.first_name)
+            .bind(patch.last_name)
+            .bind(id.to_str())
+            .execute(&self.connection)
+            .await;
+        match res {
+            Ok(res) if res.rows_affected() == 0 => Err(ServiceError::ObjectNotFound),
+            Ok(_) => Ok(()),
+            Err(e) if get_db_err_code(&e).await == 23505 => {
+                event!(Level::WARN, "{}", e);
+                Err(ServiceError::ConflictInDb)
+            }
+            Err(e) => {
+                event!(Level::ERROR, "Update user query failed: {}", e);
+                Err(ServiceError::DbQueryError)
+            }
+        }
+    }
+
+    async fn set_user_active(&self, id: Id, active: bool) -> Result<(), ServiceError> {
+        let res = sqlx::query("UPDATE users SET deactivated_at = CASE WHEN $1 THEN NULL ELSE COALESCE(deactivated_at, NOW()) END WHERE _id = uuid_or_null($2);")
+            .bind(active)
+            .bind(id.to_str())
+            .execute(&self.connection)
+            .await;
+        match res {
+            Ok(res) if res.rows_affected() == 0 => Err(ServiceError::ObjectNotFound),
+            Ok(_) => Ok(()),
+            Err(e) => {
+                event!(Level::ERROR, "Set user active query failed: {}", e);
+                Err(ServiceError::DbQueryError)
+            }
+        }
+    }
+
+    /// Users who still author questions or messages cannot be deleted, deactivate them instead.
+    async fn delete_user(&self, id: Id) -> Result<(), ServiceError> {
+        let res = sqlx::query("DELETE FROM users WHERE _id = uuid_or_null($1);")
+            .bind(id.to_str())
+            .execute(&self.connection)
+            .await;
+        match res {
+            Ok(res) if res.rows_affected() == 0 => Err(ServiceError::ObjectNotFound),
+            Ok(_) => Ok(()),
+            Err(e) if get_db_err_code(&e).await == 23503 => {
+                event!(Level::WARN, "{}", e);
+                Err(ServiceError::ConflictInDb)
+            }
+            Err(e) => {
+                event!(Level::ERROR, "Delete user query failed: {}", e);
+                Err(ServiceError::DbQueryError)
+            }
+        }
+    }
+
+    /// Only replaces the password when `current_password` matches the stored one.
+    async fn change_password(&self, id: Id, current_password: String, new_password: String) -> Result<(), ServiceError> {
+        let res = sqlx::query("UPDATE users SET password = crypt($1, gen_salt('bf', 8)) WHERE _id = uuid_or_null($2) AND password = crypt($3, password);")
+            .bind(new_password)
+            .bind(id.to_str())
+            .bind(current_password)
+            .execute(&self.connection)
+            .await;
+        match res {
+            Ok(res) if res.rows_affected() == 0 => Err(ServiceError::AuthCredsMissing),
+            Ok(_) => Ok(()),
+            Err(e) => {
+                event!(Level::ERROR, "Change password query failed: {}", e);
+                Err(ServiceError::DbQueryError)
+            }
+        }
+    }
+
+    async fn get_active_user_by_email(&self, email: String) -> Result<UserOut, ServiceError> {
+        let res = sqlx::query("SELECT _id::text, created_at::text, email, first_name, last_name, role::text, deactivated_at::text, email_verified_at::text FROM users WHERE email = $1 AND deactivated_at IS NULL;")
+            .bind(email)
+            .map(|row: PgRow| user_from_row(&row))
+            .fetch_optional(&self.connection)
+            .await;
+        match res {
+            Ok(Some(user)) => Ok(user),
+            Ok(None) => Err(ServiceError::ObjectNotFound),
+            Err(e) => {
+                event!(Level::ERROR, "Get user by email query failed: {}", e);
+                Err(ServiceError::DbQueryError)
+            }
+        }
+    }
+
+    async fn is_email_taken(&self, email: String) -> Result<bool, ServiceError> {
+        let res = sqlx::query("SELECT EXISTS (SELECT 1 FROM users WHERE email = $1) AS taken;")
+            .bind(email)
+            .map(|row: PgRow| row.get::<bool, _>("taken"))
+            .fetch_one(&self.connection)
+            .await;
+        match res {
+            Ok(taken) => Ok(taken),
+            Err(e) => {
+                event!(Level::ERROR, "Check email taken query failed: {}", e);
+                Err(ServiceError::DbQueryError)
+            }
+        }
+    }
+
+    async fn mark_email_verified(&self, id: Id) -> Result<(), ServiceError> {
+        let res =
+            sqlx::query("UPDATE users SET email_verified_at = COALESCE(email_verified_at, NOW()) WHERE _id = uuid_or_null($1);")
+                .bind(id.to_str())
+                .execute(&self.connection)
+                .await;
+        match res {
+            Ok(res) if res.rows_affected() == 0 => Err(ServiceError::ObjectNotFound),
+            Ok(_) => Ok(()),
+            Err(e) => {
+                event!(Level::ERROR, "Mark email verified query failed: {}", e);
+                Err(ServiceError::DbQueryError)
+            }
+        }
+    }
 }
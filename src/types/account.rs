@@ -0,0 +1,100 @@
+use serde::Deserialize;
+use validator::Validate;
+
+/// What a single-use account token was issued for, tokens only work for their own purpose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountTokenPurpose {
+    EmailChange,
+    PasswordReset,
+    EmailVerification,
+}
+
+impl AccountTokenPurpose {
+    pub fn to_str(self) -> String {
+        match self {
+            Self::EmailChange => "email_change".to_string(),
+            Self::PasswordReset => "password_reset".to_string(),
+            Self::EmailVerification => "email_verification".to_string(),
+        }
+    }
+
+    pub fn ttl_mins(self) -> i32 {
+        match self {
+            Self::EmailChange => 60 * 24,
+            Self::PasswordReset => 30,
+            Self::EmailVerification => 60 * 48,
+        }
+    }
+}
+
+/// Whether users have to verify their email before asking questions, set with the `auth.email_verification` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerificationPolicy {
+    #[default]
+    Optional,
+    Required,
+}
+
+impl std::str::FromStr for VerificationPolicy {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "optional" => Ok(Self::Optional),
+            "required" => Ok(Self::Required),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "expected optional or required",
+            )),
+        }
+    }
+}
+
+/// User and, for email changes, the new address an account token was issued for.
+#[derive(Debug)]
+pub struct AccountToken {
+    pub user_id: String,
+    pub email: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct ProfilePatch {
+    #[validate(length(min = 1, max = 64, message = "must be 1 to 64 characters long"))]
+    pub first_name: Option<String>,
+    #[validate(length(min = 1, max = 64, message = "must be 1 to 64 characters long"))]
+    pub last_name: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct PasswordChangeIn {
+    pub current_password: String,
+    #[validate(custom = "crate::types::validation::password_strength")]
+    pub new_password: String,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct EmailChangeIn {
+    #[validate(
+        email(message = "must be a valid email"),
+        length(max = 64, message = "must be at most 64 characters long")
+    )]
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct PasswordForgotIn {
+    pub email: String,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct PasswordResetIn {
+    pub token: String,
+    #[validate(custom = "crate::types::validation::password_strength")]
+    pub new_password: String,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct AccountTokenIn {
+    pub token: String,
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// How new questions get an assignee, set with the `questions.auto_assign` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AssignmentStrategy {
+    /// Questions stay unassigned until claimed or assigned by hand.
+    #[default]
+    Off,
+    /// Staff members take turns, the one whose last assignment is the oldest goes next.
+    RoundRobin,
+    /// The staff member with the fewest open questions goes next.
+    LeastLoaded,
+}
+
+impl std::str::FromStr for AssignmentStrategy {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "round_robin" => Ok(Self::RoundRobin),
+            "least_loaded" => Ok(Self::LeastLoaded),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "expected off, round_robin or least_loaded",
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AssigneeIn {
+    pub assignee: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StaffWorkloadOut {
+    pub _id: String,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub open_questions: i64,
+    pub assigned_questions: i64,
+}
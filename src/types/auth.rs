@@ -1,19 +1,69 @@
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
-#[derive(Deserialize, Debug)]
+use super::role::Role;
+use super::user::UserTknDetails;
+
+#[derive(Deserialize, Debug, Validate)]
 pub struct Creds {
+    /// Only the length is checked, accounts created before emails were validated must still be able to log in.
+    #[validate(length(min = 1, max = 64, message = "must be 1 to 64 characters long"))]
     pub email: String,
+    #[validate(length(min = 1, max = 128, message = "must be 1 to 128 characters long"))]
     pub password: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Locks an account for `base_secs` once `threshold` logins in a row failed, doubling with every further failure up to `max_secs`.
+/// A `threshold` of 0 turns the lockout off.
+#[derive(Debug, Clone, Copy)]
+pub struct LoginLockout {
+    pub threshold: i32,
+    pub base_secs: i64,
+    pub max_secs: i64,
+}
+
+impl LoginLockout {
+    /// How long to lock the account for after `failures` failed logins in a row.
+    pub fn lock_secs(&self, failures: i32) -> Option<i64> {
+        if self.threshold <= 0 || failures < self.threshold {
+            return None;
+        }
+        let doublings = (failures - self.threshold).min(32) as u32;
+        Some(self.base_secs.saturating_mul(1 << doublings).min(self.max_secs))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Claims {
     pub exp: usize,
+    /// Fractional seconds, for a session revocation to tell apart the tokens issued right before and after it.
+    pub iat: f64,
+    pub jti: String,
     pub sub: String,
-    pub moderator: bool,
+    pub role: Role,
+}
+
+impl Claims {
+    pub fn user_details(&self) -> UserTknDetails {
+        UserTknDetails {
+            _id: self.sub.clone(),
+            role: self.role,
+        }
+    }
 }
 
 #[derive(Serialize, Debug)]
 pub struct Token {
     pub token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RefreshTokenIn {
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize, Debug, Default, Validate)]
+pub struct LogoutIn {
+    pub refresh_token: Option<String>,
 }
@@ -0,0 +1,57 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag telling `GET /readyz` the service is shutting down and shouldn't get new traffic.
+#[derive(Debug, Clone, Default)]
+pub struct Readiness {
+    shutting_down: Arc<AtomicBool>,
+}
+
+impl Readiness {
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Serialize)]
+pub struct CheckOut {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl CheckOut {
+    pub fn ok(name: &'static str) -> Self {
+        CheckOut {
+            name,
+            ok: true,
+            detail: None,
+        }
+    }
+
+    pub fn failed(name: &'static str, detail: impl ToString) -> Self {
+        CheckOut {
+            name,
+            ok: false,
+            detail: Some(detail.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReadinessOut {
+    pub ready: bool,
+    pub checks: Vec<CheckOut>,
+}
+
+#[derive(Serialize)]
+pub struct VersionOut {
+    pub version: &'static str,
+    pub git_sha: &'static str,
+}
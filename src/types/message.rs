@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MsgType {
+    Request,
+    Response,
+    Other,
+}
+
+impl std::str::FromStr for MsgType {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "request" => Ok(Self::Request),
+            "response" => Ok(Self::Response),
+            "other" => Ok(Self::Other),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Message type not supported",
+            )),
+        }
+    }
+}
+
+impl MsgType {
+    pub fn to_str(self) -> String {
+        match self {
+            Self::Request => "request".to_string(),
+            Self::Response => "response".to_string(),
+            Self::Other => "other".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct MsgIn {
+    #[validate(length(min = 1, max = 10000, message = "must be 1 to 10000 characters long"))]
+    pub content: String,
+}
+
+impl MsgIn {
+    pub fn authored_by(self, user_id: String, question_id: String, kind: MsgType) -> MsgByUser {
+        MsgByUser {
+            content: self.content,
+            kind,
+            question_id,
+            user_id,
+        }
+    }
+}
+
+pub struct MsgByUser {
+    pub content: String,
+    pub kind: MsgType,
+    pub question_id: String,
+    pub user_id: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MsgOut {
+    pub _id: String,
+    pub created_at: String,
+    pub content: String,
+    pub kind: MsgType,
+    pub question: String,
+    pub author: String,
+}
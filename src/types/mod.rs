@@ -1,5 +1,13 @@
+pub mod account;
+pub mod assignment;
 pub mod auth;
+pub mod health;
+pub mod message;
+pub mod moderation;
 pub mod pagination;
+pub mod query;
 pub mod question;
+pub mod role;
 pub mod shared;
 pub mod user;
+pub mod validation;
@@ -0,0 +1,20 @@
+use crate::types::shared::Id;
+use serde::Serialize;
+
+/// What a moderation queue entry is about, a question's title and content get reviewed together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewSubject {
+    Question(Id),
+    Message(Id),
+}
+
+#[derive(Serialize)]
+pub struct ReviewItemOut {
+    pub _id: String,
+    pub created_at: String,
+    /// The question under review, or the one the message belongs to.
+    pub question: Option<String>,
+    pub message: Option<String>,
+    pub title: Option<String>,
+    pub content: String,
+}
@@ -1,7 +1,24 @@
+use chrono::NaiveDateTime;
 use error_handling::ServiceError;
+use serde::Serialize;
 use std::collections::HashMap;
 
-#[derive(Default)]
+pub const DEFAULT_PAGE_SIZE: i32 = 20;
+pub const MAX_PAGE_SIZE: i32 = 100;
+
+/// Parses a cursor mode `limit` parameter, which has to be between 1 and `MAX_PAGE_SIZE`.
+pub fn parse_limit(val: &str) -> Result<i32, ServiceError> {
+    let limit = val.parse::<u32>().map_err(ServiceError::ParseError)?;
+    match (1..=MAX_PAGE_SIZE as u32).contains(&limit) {
+        true => Ok(limit as i32),
+        false => Err(ServiceError::InvalidParam(format!(
+            "limit must be between 1 and {}, got {}",
+            MAX_PAGE_SIZE, limit
+        ))),
+    }
+}
+
+#[derive(Default, Debug)]
 pub struct Pagination {
     pub offset: i32,
     pub limit: Option<i32>,
@@ -27,15 +44,122 @@ impl Pagination {
             .unwrap()
             .parse::<u32>()
             .map_err(ServiceError::ParseError)?;
+        let offset = i32::try_from(offset)
+            .map_err(|_| ServiceError::InvalidParam(format!("offset must be at most {}, got {}", i32::MAX, offset)))?;
+        // offset mode never capped the page size, clients relying on that keep working
         let limit = params
             .get("limit")
             .unwrap()
             .parse::<u32>()
             .map_err(ServiceError::ParseError)?;
+        let limit = i32::try_from(limit).unwrap_or(i32::MAX);
 
         Ok(Self {
-            offset: offset as i32,
-            limit: Some(limit as i32),
+            offset,
+            limit: Some(limit),
+        })
+    }
+}
+
+/// Position of a row in the `(created_at, _id)` keyset order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: String,
+    pub id: String,
+}
+
+impl Cursor {
+    /// Cursors are handed out as opaque hex strings so that clients don't start building them by hand.
+    pub fn encode(&self) -> String {
+        format!("{}|{}", self.created_at, self.id)
+            .bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub fn decode(encoded: &str) -> Result<Self, ServiceError> {
+        let invalid = || ServiceError::InvalidParam(format!("invalid cursor '{}'", encoded));
+        if !encoded.len().is_multiple_of(2) || !encoded.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..encoded.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&encoded[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        let decoded = String::from_utf8(bytes).map_err(|_| invalid())?;
+        let (created_at, id) = decoded.split_once('|').ok_or_else(invalid)?;
+        NaiveDateTime::parse_from_str(created_at, "%Y-%m-%d %H:%M:%S%.f").map_err(|_| invalid())?;
+        uuid::Uuid::parse_str(id).map_err(|_| invalid())?;
+        Ok(Cursor {
+            created_at: created_at.to_string(),
+            id: id.to_string(),
         })
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeysetPosition {
+    First,
+    After(Cursor),
+    Before(Cursor),
+}
+
+#[derive(Debug, Clone)]
+pub struct Keyset {
+    pub position: KeysetPosition,
+    pub limit: i32,
+}
+
+#[derive(Serialize)]
+pub struct Page<T: Serialize> {
+    pub items: Vec<T>,
+    pub next: Option<String>,
+    pub prev: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(offset: &str, limit: &str) -> HashMap<String, String> {
+        HashMap::from([
+            ("offset".to_string(), offset.to_string()),
+            ("limit".to_string(), limit.to_string()),
+        ])
+    }
+
+    #[test]
+    fn parse_limit_accepts_page_sizes_up_to_the_maximum() {
+        assert_eq!(parse_limit("1").unwrap(), 1);
+        assert_eq!(parse_limit("100").unwrap(), MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn parse_limit_rejects_page_sizes_out_of_range() {
+        for limit in ["0", "101", "4294967295"] {
+            assert!(matches!(parse_limit(limit), Err(ServiceError::InvalidParam(_))), "{}", limit);
+        }
+        assert!(matches!(parse_limit("-1"), Err(ServiceError::ParseError(_))));
+    }
+
+    #[test]
+    fn parse_from_map_rejects_offsets_that_overflow() {
+        assert!(matches!(
+            Pagination::parse_from_map(params("4294967295", "10")),
+            Err(ServiceError::InvalidParam(_))
+        ));
+        assert_eq!(Pagination::parse_from_map(params("5", "10")).unwrap().offset, 5);
+    }
+
+    #[test]
+    fn parse_from_map_keeps_page_sizes_uncapped() {
+        assert_eq!(Pagination::parse_from_map(params("0", "500")).unwrap().limit, Some(500));
+        assert_eq!(
+            Pagination::parse_from_map(params("0", "4294967295")).unwrap().limit,
+            Some(i32::MAX)
+        );
+    }
+}
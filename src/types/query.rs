@@ -0,0 +1,231 @@
+use super::pagination::{parse_limit, Cursor, Keyset, KeysetPosition, Pagination, DEFAULT_PAGE_SIZE};
+use super::question::QuestStatus;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use error_handling::ServiceError;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const KNOWN_PARAMS: [&str; 13] = [
+    "offset",
+    "limit",
+    "after",
+    "before",
+    "count",
+    "q",
+    "status",
+    "tags",
+    "tags_match",
+    "author",
+    "created_after",
+    "created_before",
+    "sort",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagsMatch {
+    Any,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestSort {
+    CreatedAtAsc,
+    CreatedAtDesc,
+    StatusAsc,
+    StatusDesc,
+}
+
+impl QuestSort {
+    pub fn as_sql(self) -> &'static str {
+        match self {
+            Self::CreatedAtAsc => "created_at ASC, id ASC",
+            Self::CreatedAtDesc => "created_at DESC, id DESC",
+            Self::StatusAsc => "status ASC, created_at DESC, id DESC",
+            Self::StatusDesc => "status DESC, created_at DESC, id DESC",
+        }
+    }
+}
+
+impl FromStr for QuestSort {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "created_at" => Ok(Self::CreatedAtAsc),
+            "-created_at" => Ok(Self::CreatedAtDesc),
+            "status" => Ok(Self::StatusAsc),
+            "-status" => Ok(Self::StatusDesc),
+            _ => Err(ServiceError::InvalidParam(format!(
+                "sort must be one of created_at, -created_at, status, -status, got '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Typed and validated query string of the questions listing endpoint.
+#[derive(Debug, Default)]
+pub struct QuestQuery {
+    pub pagination: Pagination,
+    pub keyset: Option<Keyset>,
+    pub with_total: bool,
+    pub search: Option<String>,
+    pub status: Vec<QuestStatus>,
+    pub tags: Vec<String>,
+    pub tags_match: Option<TagsMatch>,
+    pub author: Option<String>,
+    pub created_after: Option<NaiveDateTime>,
+    pub created_before: Option<NaiveDateTime>,
+    pub sort: Option<QuestSort>,
+}
+
+fn split_list(val: &str) -> Vec<String> {
+    val.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Accepts either an RFC 3339 timestamp or a plain `YYYY-MM-DD` date (midnight).
+fn parse_timestamp(name: &str, val: &str) -> Result<NaiveDateTime, ServiceError> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(val) {
+        return Ok(dt.naive_utc());
+    }
+    NaiveDate::parse_from_str(val, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .ok_or_else(|| {
+            ServiceError::InvalidParam(format!(
+                "{} must be an RFC 3339 timestamp or a YYYY-MM-DD date, got '{}'",
+                name, val
+            ))
+        })
+}
+
+impl QuestQuery {
+    /// ## Example usage
+    /// ```rust
+    /// let mut query_string_params = HashMap::new();
+    /// query_string_params.insert("status".to_string(), "Pending,Unresolved".to_string());
+    /// query_string_params.insert("sort".to_string(), "-created_at".to_string());
+    /// let query = types::query::QuestQuery::parse_from_map(query_string_params).unwrap();
+    /// assert_eq!(query.status.len(), 2);
+    /// ```
+    pub fn parse_from_map(mut params: HashMap<String, String>) -> Result<Self, ServiceError> {
+        if let Some(unknown) = params.keys().find(|key| !KNOWN_PARAMS.contains(&key.as_str())) {
+            return Err(ServiceError::UnknownParam(unknown.clone()));
+        }
+
+        let mut query = QuestQuery::default();
+
+        query.search = params.remove("q").filter(|q| !q.trim().is_empty());
+        query.keyset = Self::parse_keyset(&mut params, query.search.is_some())?;
+
+        let mut pagination_params = HashMap::new();
+        for key in ["offset", "limit"] {
+            if let Some(val) = params.remove(key) {
+                pagination_params.insert(key.to_string(), val);
+            }
+        }
+        if !pagination_params.is_empty() {
+            query.pagination = Pagination::parse_from_map(pagination_params)?;
+        }
+
+        if let Some(val) = params.remove("count") {
+            query.with_total = match val.as_str() {
+                "true" => true,
+                "false" => false,
+                _ => {
+                    return Err(ServiceError::InvalidParam(format!(
+                        "count must be true or false, got '{}'",
+                        val
+                    )))
+                }
+            };
+        }
+
+        if let Some(val) = params.remove("status") {
+            query.status = split_list(&val)
+                .iter()
+                .map(|status| {
+                    QuestStatus::from_str(status)
+                        .map_err(|_| ServiceError::InvalidParam(format!("unsupported status '{}'", status)))
+                })
+                .collect::<Result<_, _>>()?;
+        }
+
+        if let Some(val) = params.remove("tags") {
+            query.tags = split_list(&val);
+        }
+
+        if let Some(val) = params.remove("tags_match") {
+            query.tags_match = match val.as_str() {
+                "any" => Some(TagsMatch::Any),
+                "all" => Some(TagsMatch::All),
+                _ => {
+                    return Err(ServiceError::InvalidParam(format!(
+                        "tags_match must be either any or all, got '{}'",
+                        val
+                    )))
+                }
+            };
+        }
+
+        if let Some(val) = params.remove("author") {
+            uuid::Uuid::parse_str(&val)
+                .map_err(|_| ServiceError::InvalidParam(format!("author must be a user id, got '{}'", val)))?;
+            query.author = Some(val);
+        }
+
+        if let Some(val) = params.remove("created_after") {
+            query.created_after = Some(parse_timestamp("created_after", &val)?);
+        }
+        if let Some(val) = params.remove("created_before") {
+            query.created_before = Some(parse_timestamp("created_before", &val)?);
+        }
+        if let (Some(after), Some(before)) = (query.created_after, query.created_before) {
+            if after > before {
+                return Err(ServiceError::InvalidParamsRange);
+            }
+        }
+
+        if let Some(val) = params.remove("sort") {
+            query.sort = Some(QuestSort::from_str(&val)?);
+        }
+        if query.keyset.is_some() && query.sort.map(|sort| sort != QuestSort::CreatedAtAsc).unwrap_or(false) {
+            return Err(ServiceError::InvalidParam(
+                "cursor pagination only supports sort=created_at".to_string(),
+            ));
+        }
+
+        Ok(query)
+    }
+
+    /// Cursor (keyset) pagination is used whenever `after` or `before` is given, or when `limit`
+    /// comes without `offset`. Offset pagination is kept for `offset` + `limit` and for search.
+    fn parse_keyset(params: &mut HashMap<String, String>, is_search: bool) -> Result<Option<Keyset>, ServiceError> {
+        let after = params.remove("after");
+        let before = params.remove("before");
+        let position = match (after, before) {
+            (Some(_), Some(_)) => {
+                return Err(ServiceError::InvalidParam(
+                    "after and before cannot be used together".to_string(),
+                ))
+            }
+            (Some(after), None) => KeysetPosition::After(Cursor::decode(&after)?),
+            (None, Some(before)) => KeysetPosition::Before(Cursor::decode(&before)?),
+            (None, None) if params.contains_key("limit") && !params.contains_key("offset") && !is_search => KeysetPosition::First,
+            (None, None) => return Ok(None),
+        };
+        if is_search || params.contains_key("offset") {
+            return Err(ServiceError::InvalidParam(
+                "cursor pagination cannot be combined with offset or q".to_string(),
+            ));
+        }
+        let limit = match params.remove("limit") {
+            None => DEFAULT_PAGE_SIZE,
+            Some(val) => parse_limit(&val)?,
+        };
+        Ok(Some(Keyset { position, limit }))
+    }
+}
@@ -1,6 +1,8 @@
+use error_handling::ServiceError;
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum QuestStatus {
     Resolved,
     Unresolved,
@@ -23,7 +25,7 @@ impl std::str::FromStr for QuestStatus {
 }
 
 impl QuestStatus {
-    fn to_str(self) -> String {
+    pub fn to_str(self) -> String {
         match self {
             Self::Resolved => "Resolved".to_string(),
             Self::Unresolved => "Unresolved".to_string(),
@@ -31,12 +33,65 @@ impl QuestStatus {
             Self::Canceled => "Canceled".to_string(),
         }
     }
+
+    /// Who may move a question from this status to `to`:
+    /// agents drive the ticket, while its author may only cancel it or reopen it.
+    pub fn allowed_actors(self, to: QuestStatus) -> &'static [StatusActor] {
+        use QuestStatus::*;
+        use StatusActor::*;
+        match (self, to) {
+            (Pending, Unresolved) | (Pending, Resolved) => &[Agent],
+            (Pending, Canceled) => &[Agent, Author],
+            (Unresolved, Pending) | (Unresolved, Resolved) => &[Agent],
+            (Unresolved, Canceled) => &[Agent, Author],
+            (Resolved, Unresolved) => &[Agent, Author],
+            (Canceled, Pending) => &[Agent, Author],
+            _ => &[],
+        }
+    }
+
+    /// Fails with `InvalidStatusTransition` for transitions nobody may make
+    /// and with `PermissionDenied` when only other actors may make it.
+    pub fn check_transition(self, to: QuestStatus, actor: StatusActor) -> Result<(), ServiceError> {
+        let allowed = self.allowed_actors(to);
+        if allowed.is_empty() {
+            return Err(ServiceError::InvalidStatusTransition(format!(
+                "{} -> {} is not a valid transition",
+                self.to_str(),
+                to.to_str()
+            )));
+        }
+        match allowed.contains(&actor) {
+            true => Ok(()),
+            false => Err(ServiceError::PermissionDenied),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusActor {
+    Agent,
+    Author,
+    Other,
+}
+
+impl StatusActor {
+    pub fn of(user_id: &str, is_agent: bool, quest: &QuestOut) -> Self {
+        match (is_agent, quest.author == user_id) {
+            (true, _) => Self::Agent,
+            (false, true) => Self::Author,
+            (false, false) => Self::Other,
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, Validate)]
 pub struct QuestIn {
+    #[validate(length(min = 1, max = 255, message = "must be 1 to 255 characters long"))]
     pub title: String,
+    #[validate(length(min = 1, max = 10000, message = "must be 1 to 10000 characters long"))]
     pub content: String,
+    #[validate(custom = "crate::types::validation::tags")]
     pub tags: Option<Vec<String>>,
     pub status: Option<QuestStatus>,
 }
@@ -70,7 +125,7 @@ impl QuestByUser {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Debug, Clone)]
 pub struct QuestOut {
     pub _id: String,
     pub created_at: String,
@@ -79,4 +134,28 @@ pub struct QuestOut {
     pub tags: Option<Vec<String>>,
     pub status: QuestStatus,
     pub author: String,
+    pub assignee: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct QuestSearchHit {
+    #[serde(flatten)]
+    pub question: QuestOut,
+    pub rank: f32,
+    pub title_snippet: String,
+    pub content_snippet: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct StatusChangeIn {
+    pub status: QuestStatus,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StatusChangeOut {
+    pub _id: String,
+    pub created_at: String,
+    pub actor: Option<String>,
+    pub old_status: QuestStatus,
+    pub new_status: QuestStatus,
 }
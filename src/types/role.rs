@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// What a user is allowed to do, roles only differ by the permissions they grant.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Texts are stored as is, without going through the content filter.
+    BypassContentFilter,
+    /// Work on tickets: move them through statuses, answer them, claim them and keep an own queue.
+    HandleQuestions,
+    EditAnyQuestion,
+    DeleteAnyQuestion,
+    ReviewContent,
+    AssignQuestions,
+    RevokeSessions,
+    ManageRoles,
+    /// List, edit, deactivate and delete user accounts.
+    ManageUsers,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Customer,
+    Agent,
+    Moderator,
+    Admin,
+}
+
+impl std::str::FromStr for Role {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "customer" => Ok(Self::Customer),
+            "agent" => Ok(Self::Agent),
+            "moderator" => Ok(Self::Moderator),
+            "admin" => Ok(Self::Admin),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Role not supported")),
+        }
+    }
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [Role::Customer, Role::Agent, Role::Moderator, Role::Admin];
+
+    pub fn to_str(self) -> String {
+        match self {
+            Self::Customer => "customer".to_string(),
+            Self::Agent => "agent".to_string(),
+            Self::Moderator => "moderator".to_string(),
+            Self::Admin => "admin".to_string(),
+        }
+    }
+
+    pub fn permissions(self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            Self::Customer => &[],
+            Self::Agent => &[HandleQuestions],
+            Self::Moderator => &[
+                BypassContentFilter,
+                HandleQuestions,
+                EditAnyQuestion,
+                DeleteAnyQuestion,
+                ReviewContent,
+                AssignQuestions,
+                RevokeSessions,
+            ],
+            Self::Admin => &[
+                BypassContentFilter,
+                HandleQuestions,
+                EditAnyQuestion,
+                DeleteAnyQuestion,
+                ReviewContent,
+                AssignQuestions,
+                RevokeSessions,
+                ManageRoles,
+                ManageUsers,
+            ],
+        }
+    }
+
+    pub fn can(self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+
+    /// Names of the roles granting `permission`, as stored in the `users.role` column.
+    pub fn granting(permission: Permission) -> Vec<String> {
+        Self::ALL
+            .into_iter()
+            .filter(|role| role.can(permission))
+            .map(|role| role.to_str())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct RoleOut {
+    pub role: Role,
+    pub permissions: &'static [Permission],
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RoleIn {
+    pub role: Role,
+}
@@ -1,28 +1,68 @@
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
-#[derive(Deserialize, Debug)]
+use super::role::{Permission, Role};
+
+#[derive(Deserialize, Debug, Validate)]
 pub struct UserIn {
+    #[validate(
+        email(message = "must be a valid email"),
+        length(max = 64, message = "must be at most 64 characters long")
+    )]
     pub email: String,
+    #[validate(custom = "crate::types::validation::password_strength")]
     pub password: String,
+    #[validate(length(min = 1, max = 64, message = "must be 1 to 64 characters long"))]
     pub first_name: String,
+    #[validate(length(min = 1, max = 64, message = "must be 1 to 64 characters long"))]
     pub last_name: String,
     pub is_moderator: Option<bool>,
 }
 
-#[derive(Serialize)]
+impl UserIn {
+    /// Sign up creates customers, moderators are only created with the moderator auth key.
+    pub fn role(&self) -> Role {
+        match self.is_moderator.unwrap_or(false) {
+            true => Role::Moderator,
+            false => Role::Customer,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
 pub struct UserOut {
     pub _id: String,
     pub created_at: String,
     pub email: String,
     pub first_name: String,
     pub last_name: String,
-    pub is_moderator: bool,
-    pub is_staff: bool,
-    pub is_superuser: bool,
+    pub role: Role,
+    pub deactivated_at: Option<String>,
+    pub email_verified_at: Option<String>,
 }
 
-#[derive(Deserialize)]
+/// Fields an admin may change on an existing user, missing ones are left as they are.
+#[derive(Deserialize, Debug, Validate)]
+pub struct UserPatch {
+    #[validate(
+        email(message = "must be a valid email"),
+        length(max = 64, message = "must be at most 64 characters long")
+    )]
+    pub email: Option<String>,
+    #[validate(length(min = 1, max = 64, message = "must be 1 to 64 characters long"))]
+    pub first_name: Option<String>,
+    #[validate(length(min = 1, max = 64, message = "must be 1 to 64 characters long"))]
+    pub last_name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
 pub struct UserTknDetails {
     pub _id: String,
-    pub is_moderator: bool,
+    pub role: Role,
+}
+
+impl UserTknDetails {
+    pub fn can(&self, permission: Permission) -> bool {
+        self.role.can(permission)
+    }
 }
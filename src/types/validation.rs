@@ -0,0 +1,119 @@
+use error_handling::{FieldError, ServiceError};
+use validator::{ValidationError, ValidationErrors};
+
+pub const PASSWORD_MIN_LEN: usize = 8;
+pub const PASSWORD_MAX_LEN: usize = 128;
+pub const MAX_TAGS: usize = 10;
+pub const MAX_TAG_LEN: usize = 32;
+
+fn invalid(code: &'static str, message: String) -> ValidationError {
+    let mut err = ValidationError::new(code);
+    err.message = Some(message.into());
+    err
+}
+
+/// At least `PASSWORD_MIN_LEN` characters, mixing letters and digits.
+pub fn password_strength(password: &str) -> Result<(), ValidationError> {
+    let len = password.chars().count();
+    if !(PASSWORD_MIN_LEN..=PASSWORD_MAX_LEN).contains(&len) {
+        return Err(invalid(
+            "password_length",
+            format!("must be {} to {} characters long", PASSWORD_MIN_LEN, PASSWORD_MAX_LEN),
+        ));
+    }
+    if !password.chars().any(char::is_alphabetic) || !password.chars().any(|c| c.is_ascii_digit()) {
+        return Err(invalid(
+            "password_strength",
+            "must contain both letters and digits".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Up to `MAX_TAGS` tags made of letters, digits, `-` and `_`.
+pub fn tags(tags: &[String]) -> Result<(), ValidationError> {
+    if tags.len() > MAX_TAGS {
+        return Err(invalid("tags_count", format!("at most {} tags are allowed", MAX_TAGS)));
+    }
+    let valid_tag = |tag: &String| {
+        (1..=MAX_TAG_LEN).contains(&tag.chars().count()) && tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    };
+    if let Some(tag) = tags.iter().find(|tag| !valid_tag(tag)) {
+        return Err(invalid(
+            "tag_charset",
+            format!("tag '{}' must be 1 to {} letters, digits, '-' or '_'", tag, MAX_TAG_LEN),
+        ));
+    }
+    Ok(())
+}
+
+/// Flattens the validator's report into the per-field list returned to clients.
+pub fn validation_failed(errors: ValidationErrors) -> ServiceError {
+    let mut field_errors: Vec<FieldError> = errors
+        .field_errors()
+        .into_iter()
+        .flat_map(|(field, errors)| {
+            errors.iter().map(move |err| FieldError {
+                field: field.to_string(),
+                message: err
+                    .message
+                    .as_ref()
+                    .map(|msg| msg.to_string())
+                    .unwrap_or_else(|| err.code.to_string()),
+            })
+        })
+        .collect();
+    field_errors.sort_by(|a, b| a.field.cmp(&b.field));
+    ServiceError::ValidationFailed(field_errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(res: Result<(), ValidationError>) -> String {
+        res.unwrap_err().code.to_string()
+    }
+
+    fn owned(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn password_strength_accepts_letters_and_digits_within_bounds() {
+        assert!(password_strength("plan9bell").is_ok());
+        assert!(password_strength("ünïcödé1").is_ok());
+        assert!(password_strength(&format!("{}1", "a".repeat(PASSWORD_MAX_LEN - 1))).is_ok());
+    }
+
+    #[test]
+    fn password_strength_rejects_lengths_out_of_bounds() {
+        assert_eq!(code(password_strength("short1")), "password_length");
+        assert_eq!(
+            code(password_strength(&format!("{}1", "a".repeat(PASSWORD_MAX_LEN)))),
+            "password_length"
+        );
+    }
+
+    #[test]
+    fn password_strength_requires_letters_and_digits() {
+        assert_eq!(code(password_strength("onlyletters")), "password_strength");
+        assert_eq!(code(password_strength("1234567890")), "password_strength");
+    }
+
+    #[test]
+    fn tags_accepts_up_to_the_maximum_count() {
+        assert!(tags(&[]).is_ok());
+        assert!(tags(&owned(&["rust", "web-dev", "sql_db", "ünï"])).is_ok());
+        assert!(tags(&vec!["tag".to_string(); MAX_TAGS]).is_ok());
+        assert_eq!(code(tags(&vec!["tag".to_string(); MAX_TAGS + 1])), "tags_count");
+    }
+
+    #[test]
+    fn tags_rejects_empty_long_or_odd_tags() {
+        for tag in ["", "no spaces", "semi;colon", &"a".repeat(MAX_TAG_LEN + 1)] {
+            assert_eq!(code(tags(&owned(&["ok", tag]))), "tag_charset", "{:?}", tag);
+        }
+        assert!(tags(&owned(&[&"a".repeat(MAX_TAG_LEN)])).is_ok());
+    }
+}
@@ -0,0 +1,148 @@
+#!/bin/bash
+
+NETWORK_ALIAS=$1
+
+USERS_ENDPOINT="$NETWORK_ALIAS:7878/users"
+LOGIN_ENDPOINT="$NETWORK_ALIAS:7878/login"
+REFRESH_ENDPOINT="$NETWORK_ALIAS:7878/token/refresh"
+LOGOUT_ENDPOINT="$NETWORK_ALIAS:7878/logout"
+QUESTIONS_ENDPOINT="$NETWORK_ALIAS:7878/questions"
+
+CREATED_STATUS="201"
+NO_CONTENT_STATUS="204"
+UNAUTHORIZED_STATUS="401"
+
+EXIT_STATUS=0
+
+
+echo "Creating common user..."
+curl --fail --location --request POST $USERS_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "dennis.ritchie.common@gmail.com",
+    "password": "pointers1978",
+    "first_name": "Dennis",
+    "last_name": "Ritchie"
+}'
+
+
+
+echo "Obtaining tokens for common user..."
+login_resp_body=$(curl --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "dennis.ritchie.common@gmail.com",
+    "password": "pointers1978"
+}')
+capture='\([^\"]*\)'
+refresh_token_string=$(echo $login_resp_body | sed "s/{.*\"refresh_token\":\"$capture.*}/\1/g")
+
+
+
+echo "Rotating refresh token..."
+refresh_resp_body=$(curl --location --request POST $REFRESH_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw "{\"refresh_token\": \"$refresh_token_string\"}")
+rotated_refresh_token_string=$(echo $refresh_resp_body | sed "s/{.*\"refresh_token\":\"$capture.*}/\1/g")
+if [ "$rotated_refresh_token_string" == "$refresh_token_string" ] || [ -z "$rotated_refresh_token_string" ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Refresh token was not rotated. Response: $refresh_resp_body"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Replaying already rotated refresh token..."
+replay_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST $REFRESH_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw "{\"refresh_token\": \"$refresh_token_string\"}")
+if [ $replay_status_code != $UNAUTHORIZED_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Replaying rotated refresh token returned unexpected status code: $replay_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Using the latest refresh token of the revoked family..."
+revoked_family_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST $REFRESH_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw "{\"refresh_token\": \"$rotated_refresh_token_string\"}")
+if [ $revoked_family_status_code != $UNAUTHORIZED_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Refresh token family was not revoked. Status code: $revoked_family_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Logging in again and logging out..."
+login_resp_body=$(curl --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "dennis.ritchie.common@gmail.com",
+    "password": "pointers1978"
+}')
+token_string=$(echo $login_resp_body | sed "s/{.*\"token\":\"$capture.*}/\1/g")
+refresh_token_string=$(echo $login_resp_body | sed "s/{.*\"refresh_token\":\"$capture.*}/\1/g")
+logout_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST $LOGOUT_ENDPOINT \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw "{\"refresh_token\": \"$refresh_token_string\"}")
+if [ $logout_status_code != $NO_CONTENT_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Logout operation returned unexpected status code: $logout_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Using access token after logout..."
+create_question_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST $QUESTIONS_ENDPOINT \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "title": "Should never be created",
+    "content": "The token has been revoked on logout"
+}')
+if [ $create_question_status_code != $UNAUTHORIZED_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Revoked access token was accepted. Status code: $create_question_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Logging out without a body..."
+login_resp_body=$(curl --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "dennis.ritchie.common@gmail.com",
+    "password": "pointers1978"
+}')
+token_string=$(echo $login_resp_body | sed "s/{.*\"token\":\"$capture.*}/\1/g")
+logout_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST $LOGOUT_ENDPOINT \
+--header "Authorization: Token $token_string")
+if [ $logout_status_code != $NO_CONTENT_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Logout without a body returned unexpected status code: $logout_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+# RESULTS OF THE RUN
+if [ $EXIT_STATUS != 0 ]
+then
+    echo "FAILURE"
+    exit 1
+fi
+
+echo "SUCCESS"
+exit 0
@@ -0,0 +1,78 @@
+#!/bin/bash
+
+NETWORK_ALIAS=$1
+
+QUESTIONS_ENDPOINT="$NETWORK_ALIAS:7878/questions"
+UNKNOWN_ENDPOINT="$NETWORK_ALIAS:7878/no-such-route"
+
+UNAUTHORIZED_STATUS="401"
+NOT_FOUND_STATUS="404"
+METHOD_NOT_ALLOWED_STATUS="405"
+PROBLEM_CONTENT_TYPE="application/problem+json"
+
+EXIT_STATUS=0
+
+
+echo "Requesting an unknown route..."
+unknown_route_resp=$(curl -w "%{http_code}" --location --request GET $UNKNOWN_ENDPOINT)
+if [[ $unknown_route_resp != *"$NOT_FOUND_STATUS" ]] || [[ $unknown_route_resp != *"\"code\":\"route_not_found\""* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Unknown route should answer with a route_not_found problem, but got: $unknown_route_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Using a method the route doesn't support..."
+wrong_method_resp=$(curl -w "%{http_code}" --location --request PATCH $QUESTIONS_ENDPOINT)
+if [[ $wrong_method_resp != *"$METHOD_NOT_ALLOWED_STATUS" ]] || [[ $wrong_method_resp != *"\"code\":\"method_not_allowed\""* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Unsupported method should answer with a method_not_allowed problem, but got: $wrong_method_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Creating a question without a token..."
+no_token_headers=$(curl -s -D - -o /dev/null --location --request POST $QUESTIONS_ENDPOINT \
+--header 'X-Request-Id: errors-test-1' \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "title": "Anonymous",
+    "content": "Question without a token",
+    "tags": []
+}')
+no_token_resp=$(curl -w "%{http_code}" --location --request POST $QUESTIONS_ENDPOINT \
+--header 'X-Request-Id: errors-test-1' \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "title": "Anonymous",
+    "content": "Question without a token",
+    "tags": []
+}')
+if [[ $no_token_resp != *"$UNAUTHORIZED_STATUS" ]] || [[ $no_token_resp != *"\"code\":\"invalid_token\""* ]] || [[ $no_token_resp != *"\"request_id\":\"errors-test-1\""* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Missing token should answer with an invalid_token problem carrying the request id, but got: $no_token_resp"
+    EXIT_STATUS=1
+fi
+if [[ $no_token_headers != *"$PROBLEM_CONTENT_TYPE"* ]] || [[ $no_token_headers != *"errors-test-1"* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Error responses should be $PROBLEM_CONTENT_TYPE and echo the request id, but got: $no_token_headers"
+    EXIT_STATUS=1
+fi
+
+
+
+# RESULTS OF THE SELF-CLEANING RUN
+if [ $EXIT_STATUS != 0 ]
+then
+    echo "FAILURE"
+    exit 1
+fi
+
+echo "SUCCESS"
+exit 0
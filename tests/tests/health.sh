@@ -0,0 +1,52 @@
+#!/bin/bash
+
+NETWORK_ALIAS=$1
+
+HEALTHZ_ENDPOINT="$NETWORK_ALIAS:7878/healthz"
+READYZ_ENDPOINT="$NETWORK_ALIAS:7878/readyz"
+VERSION_ENDPOINT="$NETWORK_ALIAS:7878/version"
+
+OK_STATUS="200"
+
+EXIT_STATUS=0
+
+
+echo "Probing liveness..."
+healthz_resp=$(curl -w "%{http_code}" --location --request GET $HEALTHZ_ENDPOINT)
+if [[ $healthz_resp != *"$OK_STATUS" ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Liveness probe should succeed, but got: $healthz_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Probing readiness..."
+readyz_resp=$(curl -w "%{http_code}" --location --request GET $READYZ_ENDPOINT)
+if [[ $readyz_resp != *"$OK_STATUS" ]] || [[ $readyz_resp != *"\"ready\":true"* ]] || [[ $readyz_resp != *"\"name\":\"migrations\",\"ok\":true"* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Readiness probe should succeed with all migrations applied, but got: $readyz_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Asking for the version..."
+version_resp=$(curl -w "%{http_code}" --location --request GET $VERSION_ENDPOINT)
+if [[ $version_resp != *"$OK_STATUS" ]] || [[ $version_resp != *"\"version\":\""* ]] || [[ $version_resp != *"\"git_sha\":\""* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Version should carry the crate version and the git SHA, but got: $version_resp"
+    EXIT_STATUS=1
+fi
+
+if [ $EXIT_STATUS != 0 ]
+then
+    echo "FAILURE"
+    exit 1
+fi
+
+echo "SUCCESS"
+exit 0
@@ -0,0 +1,69 @@
+#!/bin/bash
+
+NETWORK_ALIAS=$1
+
+METRICS_ENDPOINT="$NETWORK_ALIAS:9100/metrics"
+API_METRICS_ENDPOINT="$NETWORK_ALIAS:7878/metrics"
+ROLES_ENDPOINT="$NETWORK_ALIAS:7878/roles"
+QUESTIONS_ENDPOINT="$NETWORK_ALIAS:7878/questions"
+
+OK_STATUS="200"
+NOT_FOUND_STATUS="404"
+
+EXIT_STATUS=0
+
+
+echo "Requesting routes to be counted..."
+curl --location --request GET $ROLES_ENDPOINT > /dev/null
+curl --location --request GET "$QUESTIONS_ENDPOINT/not-an-id/messages" > /dev/null
+curl --location --request GET "$QUESTIONS_ENDPOINT/roles/users/me/queue" > /dev/null
+
+
+
+echo "Scraping the metrics on the API port..."
+api_metrics_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request GET $API_METRICS_ENDPOINT)
+if [ $api_metrics_status_code != $NOT_FOUND_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Metrics should not be served on the API port, but got status code: $api_metrics_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Scraping the metrics..."
+metrics_resp=$(curl -w "%{http_code}" --location --request GET $METRICS_ENDPOINT)
+if [[ $metrics_resp != *"$OK_STATUS" ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Metrics should be served, but got: $metrics_resp"
+    EXIT_STATUS=1
+fi
+if [[ $metrics_resp != *"http_requests_total{method=\"GET\",route=\"/roles\",status=\"200\"}"* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Metrics should count requests by route and status, but got: $metrics_resp"
+    EXIT_STATUS=1
+fi
+if [[ $metrics_resp != *"route=\"/questions/:id/messages\",status=\"401\""* ]] || [[ $metrics_resp != *"route=\"unmatched\",status=\"404\""* ]] || [[ $metrics_resp == *"route=\"/questions/roles"* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Metrics should label requests with their route name or unmatched, but got: $metrics_resp"
+    EXIT_STATUS=1
+fi
+if [[ $metrics_resp != *"db_pool_connections{state=\"idle\"}"* ]] || [[ $metrics_resp != *"questions{status=\"Pending\"}"* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Metrics should report the database pool and questions by status, but got: $metrics_resp"
+    EXIT_STATUS=1
+fi
+
+
+if [ $EXIT_STATUS != 0 ]
+then
+    echo "FAILURE"
+    exit 1
+fi
+
+echo "SUCCESS"
+exit 0
@@ -0,0 +1,205 @@
+#!/bin/bash
+
+NETWORK_ALIAS=$1
+
+USERS_ENDPOINT="$NETWORK_ALIAS:7878/users"
+LOGIN_ENDPOINT="$NETWORK_ALIAS:7878/login"
+ME_ENDPOINT="$NETWORK_ALIAS:7878/me"
+PASSWORD_ENDPOINT="$NETWORK_ALIAS:7878/password"
+
+NO_CONTENT_STATUS="204"
+ACCEPTED_STATUS="202"
+UNAUTHORIZED_STATUS="401"
+CONFLICT_STATUS="409"
+
+EXIT_STATUS=0
+
+
+echo "Creating common user..."
+curl --fail --location --request POST $USERS_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "rob.pike.profile@gmail.com",
+    "password": "plan9bell",
+    "first_name": "Rob",
+    "last_name": "Pike"
+}'
+
+
+
+echo "Obtaining token for common user..."
+login_resp_body=$(curl --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "rob.pike.profile@gmail.com",
+    "password": "plan9bell"
+}')
+capture='\([^\"]*\)'
+token_string=$(echo $login_resp_body | sed "s/{.*\"token\":\"$capture.*}/\1/g")
+
+
+
+echo "Renaming common user..."
+update_me_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request PATCH $ME_ENDPOINT \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "first_name": "Robert"
+}')
+if [ $update_me_status_code != $NO_CONTENT_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Update profile operation returned unexpected status code: $update_me_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Getting own profile..."
+get_me_resp=$(curl --location --request GET $ME_ENDPOINT \
+--header "Authorization: Token $token_string")
+if [[ $get_me_resp != *"\"first_name\":\"Robert\""* ]] || [[ $get_me_resp != *"\"email\":\"rob.pike.profile@gmail.com\""* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Getting profile error. Expected the renamed user, but got: $get_me_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Requesting an email change..."
+email_change_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request PUT "$ME_ENDPOINT/email" \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "rob.pike.golang@gmail.com",
+    "password": "plan9bell"
+}')
+if [ $email_change_status_code != $ACCEPTED_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Email change request returned unexpected status code: $email_change_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Requesting an email change to an email in use..."
+email_change_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request PUT "$ME_ENDPOINT/email" \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "rob.pike.profile@gmail.com",
+    "password": "plan9bell"
+}')
+if [ $email_change_status_code != $CONFLICT_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Email change to an email in use returned unexpected status code: $email_change_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Changing password with a wrong current password..."
+change_password_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request PUT "$ME_ENDPOINT/password" \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "current_password": "inferno1995",
+    "new_password": "golang2009"
+}')
+if [ $change_password_status_code != $UNAUTHORIZED_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Password change with a wrong password returned unexpected status code: $change_password_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Changing password..."
+change_password_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request PUT "$ME_ENDPOINT/password" \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "current_password": "plan9bell",
+    "new_password": "golang2009"
+}')
+if [ $change_password_status_code != $NO_CONTENT_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Password change returned unexpected status code: $change_password_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Getting own profile with a token issued before the password change..."
+get_me_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request GET $ME_ENDPOINT \
+--header "Authorization: Token $token_string")
+if [ $get_me_status_code != $UNAUTHORIZED_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Token should be revoked after a password change, but got status code: $get_me_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Asking for password resets of known and unknown emails..."
+forgot_known_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST "$PASSWORD_ENDPOINT/forgot" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "rob.pike.profile@gmail.com"
+}')
+forgot_unknown_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST "$PASSWORD_ENDPOINT/forgot" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "nobody.common@gmail.com"
+}')
+if [ $forgot_known_status_code != $ACCEPTED_STATUS ] || [ $forgot_unknown_status_code != $ACCEPTED_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Password reset requests should look alike, but got status codes: $forgot_known_status_code and $forgot_unknown_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Resetting password with an invalid token..."
+reset_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST "$PASSWORD_ENDPOINT/reset" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "token": "not-a-reset-token",
+    "new_password": "inferno1995"
+}')
+if [ $reset_status_code != $UNAUTHORIZED_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Password reset with an invalid token returned unexpected status code: $reset_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Verifying email with an invalid token..."
+verify_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request GET "$USERS_ENDPOINT/verify?token=not-a-verification-token")
+if [ $verify_status_code != $UNAUTHORIZED_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Email verification with an invalid token returned unexpected status code: $verify_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+# RESULTS OF THE SELF-CLEANING RUN
+if [ $EXIT_STATUS != 0 ]
+then
+    echo "FAILURE"
+    exit 1
+fi
+
+echo "SUCCESS"
+exit 0
@@ -0,0 +1,136 @@
+#!/bin/bash
+
+NETWORK_ALIAS=$1
+
+USERS_ENDPOINT="$NETWORK_ALIAS:7878/users"
+LOGIN_ENDPOINT="$NETWORK_ALIAS:7878/login"
+QUESTIONS_ENDPOINT="$NETWORK_ALIAS:7878/questions"
+QUEUE_ENDPOINT="$NETWORK_ALIAS:7878/queue"
+WORKLOAD_ENDPOINT="$NETWORK_ALIAS:7878/staff/workload"
+
+NO_CONTENT_STATUS="204"
+FORBIDDEN_STATUS="403"
+
+EXIT_STATUS=0
+
+
+echo "Creating common user..."
+curl --fail --location --request POST $USERS_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "dennis.ritchie.assignment@gmail.com",
+    "password": "clang1972",
+    "first_name": "Dennis",
+    "last_name": "Ritchie"
+}'
+
+
+
+echo "Obtaining token for common user..."
+login_resp_body=$(curl --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "dennis.ritchie.assignment@gmail.com",
+    "password": "clang1972"
+}')
+capture='\([^\"]*\)'
+token_string=$(echo $login_resp_body | sed "s/{.*\"token\":\"$capture.*}/\1/g")
+
+
+
+echo "Creating a new question..."
+create_question_resp=$(curl --location --request POST $QUESTIONS_ENDPOINT \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "title": "Question waiting for an agent",
+    "content": "Customer question nobody has picked up yet"
+}')
+new_question_id=$(echo $create_question_resp | sed "s/{.*\"_id\":\"$capture.*}/\1/g")
+
+
+
+echo "Getting question with id $new_question_id"
+get_question_resp=$(curl --location --request GET "$QUESTIONS_ENDPOINT/$new_question_id")
+if [[ $get_question_resp != *"\"assignee\":null"* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "New question should not be assigned, but got: $get_question_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Claiming question with id $new_question_id as a customer..."
+claim_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST "$QUESTIONS_ENDPOINT/$new_question_id/claim" \
+--header "Authorization: Token $token_string")
+if [ $claim_status_code != $FORBIDDEN_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Customer should not be able to claim a question, but got status code: $claim_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Assigning question with id $new_question_id as a customer..."
+assign_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request PUT "$QUESTIONS_ENDPOINT/$new_question_id/assignee" \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw "{
+    \"assignee\": \"$new_question_id\"
+}")
+if [ $assign_status_code != $FORBIDDEN_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Customer should not be able to assign a question, but got status code: $assign_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Getting own queue as a customer..."
+queue_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request GET $QUEUE_ENDPOINT \
+--header "Authorization: Token $token_string")
+if [ $queue_status_code != $FORBIDDEN_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Customer should not have a queue, but got status code: $queue_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Getting staff workload as a customer..."
+workload_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request GET $WORKLOAD_ENDPOINT \
+--header "Authorization: Token $token_string")
+if [ $workload_status_code != $FORBIDDEN_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Customer should not see staff workload, but got status code: $workload_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Deleting question with id $new_question_id"
+delete_question_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request DELETE "$QUESTIONS_ENDPOINT/$new_question_id" \
+--header "Authorization: Token $token_string")
+if [ $delete_question_status_code != $NO_CONTENT_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Delete question operation returned unexpected status code: $delete_question_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+# RESULTS OF THE SELF-CLEANING RUN
+if [ $EXIT_STATUS != 0 ]
+then
+    echo "FAILURE"
+    exit 1
+fi
+
+echo "SUCCESS"
+exit 0
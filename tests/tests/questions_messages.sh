@@ -0,0 +1,173 @@
+#!/bin/bash
+
+NETWORK_ALIAS=$1
+
+USERS_ENDPOINT="$NETWORK_ALIAS:7878/users"
+LOGIN_ENDPOINT="$NETWORK_ALIAS:7878/login"
+QUESTIONS_ENDPOINT="$NETWORK_ALIAS:7878/questions"
+
+OK_STATUS="200"
+CREATED_STATUS="201"
+NO_CONTENT_STATUS="204"
+UNAUTHORIZED_STATUS="401"
+FORBIDDEN_STATUS="403"
+EMPTY_BODY="[]"
+
+EXIT_STATUS=0
+
+
+echo "Creating common user..."
+curl --fail --location --request POST $USERS_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "ken.thompson.common@gmail.com",
+    "password": "unix1969",
+    "first_name": "Ken",
+    "last_name": "Thompson"
+}'
+
+
+
+echo "Obtaining token for common user..."
+login_resp_body=$(curl --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "ken.thompson.common@gmail.com",
+    "password": "unix1969"
+}')
+capture='\([^\"]*\)'
+token_string=$(echo $login_resp_body | sed "s/{.*\"token\":\"$capture.*}/\1/g")
+
+
+
+echo "Creating another common user..."
+curl --fail --location --request POST $USERS_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "brian.kernighan.outsider@gmail.com",
+    "password": "unix1969",
+    "first_name": "Brian",
+    "last_name": "Kernighan"
+}'
+other_login_resp_body=$(curl --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "brian.kernighan.outsider@gmail.com",
+    "password": "unix1969"
+}')
+other_token_string=$(echo $other_login_resp_body | sed "s/{.*\"token\":\"$capture.*}/\1/g")
+
+
+
+echo "Creating a new question..."
+create_question_resp=$(curl --location --request POST $QUESTIONS_ENDPOINT \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "title": "Question to be followed up",
+    "content": "Customer question that will receive a follow up message"
+}')
+new_question_id=$(echo $create_question_resp | sed "s/{.*\"_id\":\"$capture.*}/\1/g")
+
+
+
+echo "Listing messages for question with id $new_question_id"
+list_messages_resp=$(curl --location --request GET "$QUESTIONS_ENDPOINT/$new_question_id/messages" \
+--header "Authorization: Token $token_string")
+if [ ${#list_messages_resp} != ${#EMPTY_BODY} ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Listing messages error. Should be an empty array, but got: $list_messages_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Adding a follow up message to question with id $new_question_id"
+add_message_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST "$QUESTIONS_ENDPOINT/$new_question_id/messages" \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "content": "Any updates on this one?"
+}')
+if [ $add_message_status_code != $CREATED_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Add message operation returned unexpected status code: $add_message_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Listing messages without a token..."
+anonymous_list_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request GET "$QUESTIONS_ENDPOINT/$new_question_id/messages")
+if [ $anonymous_list_status_code != $UNAUTHORIZED_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Anonymous users should not be able to list messages, got status code: $anonymous_list_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Listing messages as a user unrelated to the question..."
+other_list_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request GET "$QUESTIONS_ENDPOINT/$new_question_id/messages" \
+--header "Authorization: Token $other_token_string")
+if [ $other_list_status_code != $FORBIDDEN_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Only the author should be able to list messages, got status code: $other_list_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Adding a message as a user unrelated to the question..."
+other_message_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST "$QUESTIONS_ENDPOINT/$new_question_id/messages" \
+--header "Authorization: Token $other_token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "content": "Chiming in on a ticket that is not mine"
+}')
+if [ $other_message_status_code != $FORBIDDEN_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Only the author should be able to add messages, got status code: $other_message_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Listing messages again..."
+list_messages_resp=$(curl --location --request GET "$QUESTIONS_ENDPOINT/$new_question_id/messages" \
+--header "Authorization: Token $token_string")
+if [[ $list_messages_resp != *"\"kind\":\"request\""* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Listing messages error. Expected a single follow up request, but got: $list_messages_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Deleting question with id $new_question_id"
+delete_question_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request DELETE "$QUESTIONS_ENDPOINT/$new_question_id" \
+--header "Authorization: Token $token_string")
+if [ $delete_question_status_code != $NO_CONTENT_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Delete question operation returned unexpected status code: $delete_question_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+# RESULTS OF THE SELF-CLEANING RUN
+if [ $EXIT_STATUS != 0 ]
+then
+    echo "FAILURE"
+    exit 1
+fi
+
+echo "SUCCESS"
+exit 0
@@ -0,0 +1,167 @@
+#!/bin/bash
+
+NETWORK_ALIAS=$1
+
+USERS_ENDPOINT="$NETWORK_ALIAS:7878/users"
+LOGIN_ENDPOINT="$NETWORK_ALIAS:7878/login"
+PASSWORD_ENDPOINT="$NETWORK_ALIAS:7878/password"
+QUESTIONS_ENDPOINT="$NETWORK_ALIAS:7878/questions"
+
+NO_CONTENT_STATUS="204"
+TOO_MANY_REQUESTS_STATUS="429"
+# defaults of LOGIN_LOCKOUT_THRESHOLD, RATE_LIMIT_QUESTIONS and RATE_LIMIT_MAIL
+LOCKOUT_THRESHOLD=5
+QUESTIONS_PER_MINUTE=10
+MAILS_PER_WINDOW=20
+
+EXIT_STATUS=0
+
+
+echo "Creating user to be locked out..."
+curl --fail --location --request POST $USERS_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "grace.hopper.lockout@gmail.com",
+    "password": "cobol1959",
+    "first_name": "Grace",
+    "last_name": "Hopper"
+}'
+
+
+
+echo "Logging in with a wrong password $LOCKOUT_THRESHOLD times..."
+for i in $(seq $LOCKOUT_THRESHOLD)
+do
+    curl -o /dev/null --location --request POST $LOGIN_ENDPOINT \
+    --header 'Content-Type: application/json' \
+    --data-raw '{
+        "email": "grace.hopper.lockout@gmail.com",
+        "password": "fortran1957"
+    }'
+done
+
+
+
+echo "Logging in with the right password while locked out..."
+locked_login_resp=$(curl -s -D - --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "grace.hopper.lockout@gmail.com",
+    "password": "cobol1959"
+}')
+unknown_login_resp=$(curl -s -D - --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "grace.hopper.unknown@gmail.com",
+    "password": "cobol1959"
+}')
+locked_status_line=$(echo "$locked_login_resp" | head -n 1)
+unknown_status_line=$(echo "$unknown_login_resp" | head -n 1)
+if [[ $locked_status_line != $unknown_status_line ]] || [[ $locked_status_line == *" 201 "* ]] || [[ $locked_login_resp == *"retry-after: "* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Locked out account should be refused like an unknown email, but got: $locked_login_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Asking for password resets until the mail rate limit kicks in..."
+for i in $(seq $((MAILS_PER_WINDOW + 1)))
+do
+    forgot_resp=$(curl -s -D - --location --request POST "$PASSWORD_ENDPOINT/forgot" \
+    --header 'Content-Type: application/json' \
+    --data-raw '{
+        "email": "grace.hopper.unknown@gmail.com"
+    }')
+done
+if [[ $forgot_resp != *" $TOO_MANY_REQUESTS_STATUS "* ]] || [[ $forgot_resp != *"retry-after: "* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Password reset requests over the rate limit should be refused with 429 and Retry-After, but got: $forgot_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Creating user to flood questions..."
+curl --fail --location --request POST $USERS_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "grace.hopper.flood@gmail.com",
+    "password": "cobol1959",
+    "first_name": "Grace",
+    "last_name": "Hopper"
+}'
+
+
+
+echo "Obtaining token for flooding user..."
+login_resp_body=$(curl --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "grace.hopper.flood@gmail.com",
+    "password": "cobol1959"
+}')
+capture='\([^\"]*\)'
+token_string=$(echo $login_resp_body | sed "s/{.*\"token\":\"$capture.*}/\1/g")
+
+
+
+echo "Creating $QUESTIONS_PER_MINUTE questions..."
+question_ids=()
+for i in $(seq $QUESTIONS_PER_MINUTE)
+do
+    create_question_resp=$(curl --location --request POST $QUESTIONS_ENDPOINT \
+    --header "Authorization: Token $token_string" \
+    --header 'Content-Type: application/json' \
+    --data-raw "{
+        \"title\": \"Question number $i\",
+        \"content\": \"One of many questions in a row\"
+    }")
+    question_ids+=($(echo $create_question_resp | sed "s/{.*\"_id\":\"$capture.*}/\1/g"))
+done
+
+
+
+echo "Creating one question too many..."
+flood_question_resp=$(curl -s -D - --location --request POST $QUESTIONS_ENDPOINT \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "title": "One too many",
+    "content": "Question over the rate limit"
+}')
+if [[ $flood_question_resp != *" $TOO_MANY_REQUESTS_STATUS "* ]] || [[ $flood_question_resp != *"retry-after: "* ]] || [[ $flood_question_resp != *"\"code\":\"rate_limited\""* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Question over the rate limit should be refused with 429 and Retry-After, but got: $flood_question_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Deleting created questions..."
+for question_id in "${question_ids[@]}"
+do
+    delete_question_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request DELETE "$QUESTIONS_ENDPOINT/$question_id" \
+    --header "Authorization: Token $token_string")
+    if [ $delete_question_status_code != $NO_CONTENT_STATUS ]
+    then
+        echo "########################## ERROR ##########################"
+        echo "Delete question operation returned unexpected status code: $delete_question_status_code"
+        EXIT_STATUS=1
+    fi
+done
+
+
+
+# RESULTS OF THE SELF-CLEANING RUN
+if [ $EXIT_STATUS != 0 ]
+then
+    echo "FAILURE"
+    exit 1
+fi
+
+echo "SUCCESS"
+exit 0
@@ -0,0 +1,98 @@
+#!/bin/bash
+
+NETWORK_ALIAS=$1
+
+USERS_ENDPOINT="$NETWORK_ALIAS:7878/users"
+LOGIN_ENDPOINT="$NETWORK_ALIAS:7878/login"
+ROLES_ENDPOINT="$NETWORK_ALIAS:7878/roles"
+
+FORBIDDEN_STATUS="403"
+
+EXIT_STATUS=0
+
+
+echo "Listing roles..."
+list_roles_resp=$(curl --location --request GET $ROLES_ENDPOINT)
+if [[ $list_roles_resp != *"\"role\":\"admin\""* ]] || [[ $list_roles_resp != *"\"manage_roles\""* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Listing roles error. Expected admin role with manage_roles permission, but got: $list_roles_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Creating common user..."
+create_user_resp=$(curl --location --request POST $USERS_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "brian.kernighan.common@gmail.com",
+    "password": "awkward1977",
+    "first_name": "Brian",
+    "last_name": "Kernighan"
+}')
+capture='\([^\"]*\)'
+user_id=$(echo $create_user_resp | sed "s/{.*\"_id\":\"$capture.*}/\1/g")
+
+
+
+echo "Obtaining token for common user..."
+login_resp_body=$(curl --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "brian.kernighan.common@gmail.com",
+    "password": "awkward1977"
+}')
+token_string=$(echo $login_resp_body | sed "s/{.*\"token\":\"$capture.*}/\1/g")
+
+
+
+echo "Promoting common user to admin as a customer..."
+set_role_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request PUT "$USERS_ENDPOINT/$user_id/role" \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "role": "admin"
+}')
+if [ $set_role_status_code != $FORBIDDEN_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Customer should not be able to change roles, but got status code: $set_role_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Listing users as a customer..."
+list_users_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request GET $USERS_ENDPOINT \
+--header "Authorization: Token $token_string")
+if [ $list_users_status_code != $FORBIDDEN_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Customer should not be able to list users, but got status code: $list_users_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Deactivating common user as a customer..."
+deactivate_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST "$USERS_ENDPOINT/$user_id/deactivate" \
+--header "Authorization: Token $token_string")
+if [ $deactivate_status_code != $FORBIDDEN_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Customer should not be able to deactivate users, but got status code: $deactivate_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
+# RESULTS OF THE SELF-CLEANING RUN
+if [ $EXIT_STATUS != 0 ]
+then
+    echo "FAILURE"
+    exit 1
+fi
+
+echo "SUCCESS"
+exit 0
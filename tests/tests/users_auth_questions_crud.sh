@@ -9,6 +9,8 @@ QUESTIONS_ENDPOINT="$NETWORK_ALIAS:7878/questions"
 OK_STATUS="200"
 CREATED_STATUS="201"
 NO_CONTENT_STATUS="204"
+FORBIDDEN_STATUS="403"
+CONFLICT_STATUS="409"
 EMPTY_BODY="[]"
 
 EXIT_STATUS=0
@@ -19,7 +21,7 @@ curl --fail --location --request POST $USERS_ENDPOINT \
 --header 'Content-Type: application/json' \
 --data-raw '{
     "email": "rob.pike.common@gmail.com",
-    "password": "concurrency",
+    "password": "concurrency1",
     "first_name": "Rob",
     "last_name": "Pike"
 }'
@@ -42,7 +44,7 @@ login_resp_body=$(curl --location --request POST $LOGIN_ENDPOINT \
 --header 'Content-Type: application/json' \
 --data-raw '{
     "email": "rob.pike.common@gmail.com",
-    "password": "concurrency"
+    "password": "concurrency1"
 }')
 echo "Login RESPONSE body: $login_resp_body"
 capture='\([^\"]*\)'
@@ -87,15 +89,31 @@ fi
 
 
 
+echo "Resolving question with id $new_question_id as its author"
+resolve_question_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST "$QUESTIONS_ENDPOINT/$new_question_id/status" \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "status": "Resolved"
+}')
+if [ $resolve_question_status_code != $FORBIDDEN_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Only moderators should be able to resolve questions. Status code: $resolve_question_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
 echo "Updating question with id $new_question_id"
 update_question_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request PUT "$QUESTIONS_ENDPOINT/$new_question_id" \
 --header "Authorization: Token $token_string" \
 --header 'Content-Type: application/json' \
 --data-raw '{
-    "title": "Title of a question/complaint/order [RESOLVED]",
+    "title": "Title of a question/complaint/order [CANCELED]",
     "content": "If updated by common user rather than moderator, all the bad words and swearing, including '\''shit'\'' will be censored with * by a 3rd party service. NB! the BadWords service we are currecntly using cannot process parenthesis",
     "tags": ["this", "string", "array", "field", "is", "optional"],
-    "status": "Resolved"
+    "status": "Canceled"
 }')
 if [ $update_question_status_code != $NO_CONTENT_STATUS ]
 then
@@ -106,6 +124,22 @@ fi
 
 
 
+echo "Resolving canceled question with id $new_question_id"
+resolve_question_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST "$QUESTIONS_ENDPOINT/$new_question_id/status" \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "status": "Resolved"
+}')
+if [ $resolve_question_status_code != $CONFLICT_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Canceled questions can't be resolved. Status code: $resolve_question_status_code"
+    EXIT_STATUS=1
+fi
+
+
+
 echo "Deleting question with id $new_question_id"
 delete_question_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request DELETE "$QUESTIONS_ENDPOINT/$new_question_id" \
 --header "Authorization: Token $token_string")
@@ -135,7 +169,7 @@ create_moderator_user_operation_status_code=$(curl -o /dev/null -w "%{http_code}
 --header 'Content-Type: application/json' \
 --data-raw '{
     "email": "rob.pike.moderator@gmail.com",
-    "password": "concurrency",
+    "password": "concurrency1",
     "first_name": "Rob",
     "last_name": "Pike",
     "is_moderator": true
@@ -153,7 +187,7 @@ login_operation_status_code=$(curl -o /dev/null -w "%{http_code}" --location --r
 --header 'Content-Type: application/json' \
 --data-raw '{
     "email": "rob.pike.moderator@gmail.com",
-    "password": "concurrency"
+    "password": "concurrency1"
 }')
 if [ $login_operation_status_code != $CREATED_STATUS ]
 then
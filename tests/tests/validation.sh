@@ -0,0 +1,97 @@
+#!/bin/bash
+
+NETWORK_ALIAS=$1
+
+USERS_ENDPOINT="$NETWORK_ALIAS:7878/users"
+LOGIN_ENDPOINT="$NETWORK_ALIAS:7878/login"
+QUESTIONS_ENDPOINT="$NETWORK_ALIAS:7878/questions"
+
+UNPROCESSABLE_STATUS="422"
+
+EXIT_STATUS=0
+
+
+echo "Creating a user with an invalid email and a weak password..."
+create_user_resp=$(curl -w "%{http_code}" --location --request POST $USERS_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "not-an-email",
+    "password": "short",
+    "first_name": "Ada",
+    "last_name": "Lovelace"
+}')
+if [[ $create_user_resp != *"$UNPROCESSABLE_STATUS" ]] || [[ $create_user_resp != *"\"field\":\"email\""* ]] || [[ $create_user_resp != *"\"field\":\"password\""* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Invalid user should be rejected with email and password errors, but got: $create_user_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+echo "Creating common user..."
+curl --fail --location --request POST $USERS_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "ada.lovelace.common@gmail.com",
+    "password": "analytical1843",
+    "first_name": "Ada",
+    "last_name": "Lovelace"
+}'
+
+
+
+echo "Obtaining token for common user..."
+login_resp_body=$(curl --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "ada.lovelace.common@gmail.com",
+    "password": "analytical1843"
+}')
+capture='\([^\"]*\)'
+token_string=$(echo $login_resp_body | sed "s/{.*\"token\":\"$capture.*}/\1/g")
+
+
+
+echo "Creating a question with an empty title and invalid tags..."
+create_question_resp=$(curl -w "%{http_code}" --location --request POST $QUESTIONS_ENDPOINT \
+--header "Authorization: Token $token_string" \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "title": "",
+    "content": "Question that never makes it to the database",
+    "tags": ["no spaces allowed"]
+}')
+if [[ $create_question_resp != *"$UNPROCESSABLE_STATUS" ]] || [[ $create_question_resp != *"\"field\":\"title\""* ]] || [[ $create_question_resp != *"\"field\":\"tags\""* ]]
+then
+    echo "########################## ERROR ##########################"
+    echo "Invalid question should be rejected with title and tags errors, but got: $create_question_resp"
+    EXIT_STATUS=1
+fi
+
+
+
+
+echo "Logging in with an email that isn't well-formed..."
+login_status_code=$(curl -o /dev/null -w "%{http_code}" --location --request POST $LOGIN_ENDPOINT \
+--header 'Content-Type: application/json' \
+--data-raw '{
+    "email": "legacy-login-name",
+    "password": "analytical1843"
+}')
+if [ $login_status_code == $UNPROCESSABLE_STATUS ]
+then
+    echo "########################## ERROR ##########################"
+    echo "Login should only validate the email length, but got status code: $login_status_code"
+    EXIT_STATUS=1
+fi
+
+# RESULTS OF THE SELF-CLEANING RUN
+if [ $EXIT_STATUS != 0 ]
+then
+    echo "FAILURE"
+    exit 1
+fi
+
+echo "SUCCESS"
+exit 0